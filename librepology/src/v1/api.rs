@@ -1,4 +1,8 @@
+use std::collections::BTreeMap;
+
+use crate::v1::error::RepologyError;
 use crate::v1::error::Result;
+use crate::v1::types::Name;
 use crate::v1::types::Package;
 use crate::v1::types::Problem;
 
@@ -13,7 +17,24 @@ use crate::v1::types::Problem;
 pub trait Api {
     fn project<N: AsRef<str>>(&self, name: N) -> Result<Vec<Package>>;
 
+    /// Get all projects which have a package in `repo`, mapped to all their packages (in all
+    /// repositories)
+    fn projects_in_repo<R: AsRef<str>>(&self, repo: R) -> Result<BTreeMap<Name, Vec<Package>>>;
+
     fn problems_for_repo<R: AsRef<str>>(&self, repo: R) -> Result<Vec<Problem>>;
 
+    /// Get the problems of `repo` page by page, each page is passed to `on_page` as soon as it is
+    /// fetched
+    ///
+    /// Backends which do not fetch the problems in pages pass all of them as one page.
+    fn problems_for_repo_paged<R, F, E>(&self, repo: R, mut on_page: F) -> std::result::Result<(), E>
+    where
+        R: AsRef<str>,
+        F: FnMut(Vec<Problem>) -> std::result::Result<(), E>,
+        E: From<RepologyError>,
+    {
+        on_page(self.problems_for_repo(repo)?)
+    }
+
     fn problems_for_maintainer<M: AsRef<str>, R: AsRef<str>>(&self, maintainer: M, repo: R) -> Result<Vec<Problem>>;
 }
@@ -1,8 +1,15 @@
+use std::collections::BTreeMap;
 use std::io::Read;
 
+use serde::de::DeserializeOwned;
+use serde::de::IgnoredAny;
+
 use crate::v1::api::Api;
+use crate::v1::envelope::Envelope;
+use crate::v1::envelope::SCHEMA_VERSION;
 use crate::v1::error::RepologyError as Error;
 use crate::v1::error::Result;
+use crate::v1::types::Name;
 use crate::v1::types::Package;
 use crate::v1::types::Problem;
 
@@ -21,18 +28,81 @@ impl BufferApi {
         trace!("Read {} bytes from stdin", read);
         Ok(BufferApi { buf })
     }
+
+    /// Parse the buffer, either as plain data or as data in an `Envelope`
+    fn parse<T: DeserializeOwned>(&self) -> Result<T> {
+        let value: serde_json::Value = serde_json::de::from_str(&self.buf)?;
+        if value.get("schema_version").is_none() {
+            return serde_json::from_value(value).map_err(Error::from);
+        }
+
+        let envelope: Envelope<T> = parse_envelope(value)?;
+        debug!(
+            "Read data fetched from {} at {}",
+            envelope.source(),
+            envelope.fetched_at()
+        );
+        Ok(envelope.into_data())
+    }
+
+    /// The envelope of the buffer without its data, `None` if the buffer holds plain data
+    ///
+    /// This tells where and when enveloped data was originally fetched.
+    pub fn envelope(&self) -> Result<Option<Envelope<()>>> {
+        let value: serde_json::Value = serde_json::de::from_str(&self.buf)?;
+        if value.get("schema_version").is_none() {
+            return Ok(None);
+        }
+
+        let envelope: Envelope<IgnoredAny> = parse_envelope(value)?;
+        Ok(Some(envelope.with_data(())))
+    }
+}
+
+/// Helper for parsing enveloped data, if this version of the library supports its schema version
+fn parse_envelope<T: DeserializeOwned>(value: serde_json::Value) -> Result<Envelope<T>> {
+    match value.get("schema_version").map(serde_json::Value::as_u64) {
+        Some(Some(version)) if version <= u64::from(SCHEMA_VERSION) => {
+            serde_json::from_value(value).map_err(Error::from)
+        }
+        version => Err(Error::UnsupportedSchemaVersion(
+            version.flatten().unwrap_or(0),
+        )),
+    }
 }
 
 impl Api for BufferApi {
     fn project<N: AsRef<str>>(&self, _name: N) -> Result<Vec<Package>> {
-        serde_json::de::from_str(&self.buf).map_err(Error::from)
+        self.parse()
+    }
+
+    fn projects_in_repo<R: AsRef<str>>(&self, repo: R) -> Result<BTreeMap<Name, Vec<Package>>> {
+        let projects: BTreeMap<Name, Vec<Package>> = self.parse()?;
+        Ok(projects
+            .into_iter()
+            .filter(|(_, packages)| packages.iter().any(|p| **p.repo() == repo.as_ref()))
+            .map(|(name, mut packages)| {
+                packages
+                    .iter_mut()
+                    .for_each(|p| p.set_effname_if_missing(&name));
+                (name, packages)
+            })
+            .collect())
     }
 
-    fn problems_for_repo<R: AsRef<str>>(&self, _repo: R) -> Result<Vec<Problem>> {
-        serde_json::de::from_str(&self.buf).map_err(Error::from)
+    fn problems_for_repo<R: AsRef<str>>(&self, repo: R) -> Result<Vec<Problem>> {
+        let mut problems: Vec<Problem> = self.parse()?;
+        problems
+            .iter_mut()
+            .for_each(|p| p.set_repo_if_missing(repo.as_ref()));
+        Ok(problems)
     }
 
-    fn problems_for_maintainer<M: AsRef<str>, R: AsRef<str>>(&self, _maintainer: M, _repo: R) -> Result<Vec<Problem>> {
-        serde_json::de::from_str(&self.buf).map_err(Error::from)
+    fn problems_for_maintainer<M: AsRef<str>, R: AsRef<str>>(&self, _maintainer: M, repo: R) -> Result<Vec<Problem>> {
+        let mut problems: Vec<Problem> = self.parse()?;
+        problems
+            .iter_mut()
+            .for_each(|p| p.set_repo_if_missing(repo.as_ref()));
+        Ok(problems)
     }
 }
@@ -0,0 +1,102 @@
+//! A versioned envelope around repology data
+//!
+//! The envelope records where and how the data was fetched, next to the data itself:
+//!
+//! ```json
+//! {
+//!   "schema_version": 1,
+//!   "query": { "command": "project", "arguments": ["project", "curl"] },
+//!   "fetched_at": "2026-10-19T08:00:00Z",
+//!   "source": "https://repology.org/",
+//!   "data": [ ... ]
+//! }
+//! ```
+//!
+//! The `BufferApi` accepts enveloped data as well as plain data.
+
+/// The schema version written by this version of the library, and the newest one it can read
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// The query the enveloped data was fetched with
+#[derive(Clone, Debug, Serialize, Deserialize, new)]
+pub struct Query {
+    /// The (sub)command that was run
+    command: String,
+
+    /// The arguments of the command
+    arguments: Vec<String>,
+}
+
+impl Query {
+    pub fn command(&self) -> &String {
+        &self.command
+    }
+
+    pub fn arguments(&self) -> &Vec<String> {
+        &self.arguments
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Envelope<T> {
+    schema_version: u32,
+    query: Query,
+
+    /// The point in time the data was fetched, in RFC 3339 format
+    fetched_at: String,
+
+    /// The URL of the repology instance the data was fetched from
+    source: String,
+
+    data: T,
+}
+
+impl Envelope<()> {
+    /// Create an envelope without data, which can be filled with `Envelope::with_data`
+    pub fn new(query: Query, fetched_at: String, source: String) -> Self {
+        Envelope {
+            schema_version: SCHEMA_VERSION,
+            query,
+            fetched_at,
+            source,
+            data: (),
+        }
+    }
+}
+
+impl<T> Envelope<T> {
+    /// Put `data` into a copy of this envelope
+    pub fn with_data<U>(&self, data: U) -> Envelope<U> {
+        Envelope {
+            schema_version: self.schema_version,
+            query: self.query.clone(),
+            fetched_at: self.fetched_at.clone(),
+            source: self.source.clone(),
+            data,
+        }
+    }
+
+    pub fn schema_version(&self) -> u32 {
+        self.schema_version
+    }
+
+    pub fn query(&self) -> &Query {
+        &self.query
+    }
+
+    pub fn fetched_at(&self) -> &String {
+        &self.fetched_at
+    }
+
+    pub fn source(&self) -> &String {
+        &self.source
+    }
+
+    pub fn data(&self) -> &T {
+        &self.data
+    }
+
+    pub fn into_data(self) -> T {
+        self.data
+    }
+}
@@ -17,6 +17,12 @@ pub enum RepologyError {
     #[error("curl error")]
     CurlError(#[from] curl::Error),
 
+    #[error("repology responded with HTTP status {0}")]
+    HttpStatus(u32),
+
+    #[error("unsupported schema version {0}, this version supports up to {}", crate::v1::envelope::SCHEMA_VERSION)]
+    UnsupportedSchemaVersion(u64),
+
     #[error("unknown error")]
     Unknown,
 }
@@ -1,5 +1,7 @@
 pub mod api;
 pub mod buffer;
+pub mod envelope;
 pub mod error;
 pub mod restapi;
 pub mod types;
+pub mod versioncmp;
@@ -1,11 +1,25 @@
+use std::cell::Cell;
+use std::collections::BTreeMap;
 use std::result::Result as RResult;
+use std::time::Duration;
+use std::time::Instant;
 
 use curl::easy::Easy2;
 
 use crate::v1::api::Api;
 use crate::v1::error::RepologyError as Error;
 use crate::v1::error::Result;
-use crate::v1::types::{Package, Problem};
+use crate::v1::types::{Name, Package, Problem};
+
+/// The number of projects repology returns per request on the `/projects/` endpoint
+const PROJECTS_PAGE_SIZE: usize = 200;
+
+/// The number of problems repology returns per request on the `/repository/<repo>/problems`
+/// endpoint
+const PROBLEMS_PAGE_SIZE: usize = 200;
+
+/// The minimum time between two requests, as requested by the repology API documentation
+const REQUEST_INTERVAL: Duration = Duration::from_secs(1);
 
 /// Private helper type for collecting data from the curl library
 struct Collector(Vec<u8>);
@@ -17,25 +31,84 @@ impl curl::easy::Handler for Collector {
 }
 
 /// Representational object for the REST Api of repology
+///
+/// Requests are rate-limited to one request per second, so this type can be used for issuing many
+/// requests without being blocked by repology.
 pub struct RestApi {
     /// Base url
     repology: String,
+
+    /// Point in time of the last request, for rate limiting
+    last_request: Cell<Option<Instant>>,
 }
 
 impl RestApi {
     pub fn new(repology: String) -> Self {
-        Self { repology }
+        Self {
+            repology,
+            last_request: Cell::new(None),
+        }
+    }
+
+    /// Helper function for waiting until the next request is allowed
+    fn wait_for_rate_limit(&self) {
+        if let Some(last) = self.last_request.get() {
+            let elapsed = last.elapsed();
+            if elapsed < REQUEST_INTERVAL {
+                let wait = REQUEST_INTERVAL - elapsed;
+                trace!("Rate limiting: waiting {:?}", wait);
+                std::thread::sleep(wait);
+            }
+        }
+        self.last_request.set(Some(Instant::now()));
     }
 
     /// Helper function for sending a request via the curl library
     fn send_request<U: AsRef<str>>(&self, request: U) -> Result<String> {
+        self.wait_for_rate_limit();
         let mut easy = Easy2::new(Collector(Vec::new()));
         easy.get(true)?;
         easy.url(request.as_ref())?;
         easy.perform()?;
+
+        let status = easy.response_code()?;
+        if !(200..300).contains(&status) {
+            return Err(Error::HttpStatus(status));
+        }
+
         let content = easy.get_ref().0.clone(); // TODO: Ugh...
         String::from_utf8(content).map_err(Error::from)
     }
+
+    /// Helper function for requesting one page of the problems of `repo`, starting at the project
+    /// `start` (from the first project if empty)
+    fn problems_page(&self, repo: &str, start: &str) -> Result<Vec<Problem>> {
+        let url = if start.is_empty() {
+            format!("{}api/v1/repository/{}/problems", self.repology, repo)
+        } else {
+            format!(
+                "{}api/v1/repository/{}/problems?start={}",
+                self.repology, repo, start
+            )
+        };
+        trace!("Request: {}", url);
+        let response = self.send_request(url)?;
+        let mut problems: Vec<Problem> = serde_json::from_str(&response)?;
+        problems.iter_mut().for_each(|p| p.set_repo_if_missing(repo));
+        Ok(problems)
+    }
+
+    /// Check whether repology knows the repository `repo`
+    ///
+    /// Only the first page of the problems of the repository is requested, repology responds with
+    /// HTTP status 404 for unknown repositories.
+    pub fn has_repository<R: AsRef<str>>(&self, repo: R) -> Result<bool> {
+        match self.problems_page(repo.as_ref(), "") {
+            Ok(_) => Ok(true),
+            Err(Error::HttpStatus(404)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
 }
 
 impl Api for RestApi {
@@ -43,18 +116,100 @@ impl Api for RestApi {
         let url = format!("{}api/v1/project/{}", self.repology, name.as_ref());
         trace!("Request: {}", url);
         let response = self.send_request(url)?;
-        serde_json::from_str(&response).map_err(Error::from)
+        let mut packages: Vec<Package> = serde_json::from_str(&response)?;
+        packages
+            .iter_mut()
+            .for_each(|p| p.set_effname_if_missing(name.as_ref()));
+        Ok(packages)
+    }
+
+    fn projects_in_repo<R: AsRef<str>>(&self, repo: R) -> Result<BTreeMap<Name, Vec<Package>>> {
+        let mut projects = BTreeMap::new();
+        let mut start = String::new();
+
+        loop {
+            // The page starting at `start` contains `start` itself, which is already known
+            let url = if start.is_empty() {
+                format!("{}api/v1/projects/?inrepo={}", self.repology, repo.as_ref())
+            } else {
+                format!(
+                    "{}api/v1/projects/{}/?inrepo={}",
+                    self.repology,
+                    start,
+                    repo.as_ref()
+                )
+            };
+            trace!("Request: {}", url);
+            let response = self.send_request(url)?;
+            let mut page: BTreeMap<Name, Vec<Package>> = serde_json::from_str(&response)?;
+            page.iter_mut().for_each(|(name, packages)| {
+                packages
+                    .iter_mut()
+                    .for_each(|p| p.set_effname_if_missing(name))
+            });
+            let next = if page.len() >= PROJECTS_PAGE_SIZE {
+                page.keys().next_back().map(|last| last.to_string())
+            } else {
+                None
+            };
+
+            projects.extend(page);
+            match next {
+                Some(next) => start = next,
+                None => break,
+            }
+        }
+
+        Ok(projects)
     }
 
     fn problems_for_repo<R: AsRef<str>>(&self, repo: R) -> Result<Vec<Problem>> {
-        let url = format!(
-            "{}api/v1/repository/{}/problems",
-            self.repology,
-            repo.as_ref()
-        );
-        trace!("Request: {}", url);
-        let response = self.send_request(url)?;
-        serde_json::from_str(&response).map_err(Error::from)
+        let mut problems = Vec::new();
+        self.problems_for_repo_paged(repo, |page| -> Result<()> {
+            problems.extend(page);
+            Ok(())
+        })?;
+        Ok(problems)
+    }
+
+    fn problems_for_repo_paged<R, F, E>(&self, repo: R, mut on_page: F) -> RResult<(), E>
+    where
+        R: AsRef<str>,
+        F: FnMut(Vec<Problem>) -> RResult<(), E>,
+        E: From<Error>,
+    {
+        let mut start = String::new();
+
+        // The problems of the project `start`, the page starting at `start` contains them again
+        let mut known: Vec<Problem> = Vec::new();
+
+        loop {
+            let page = self.problems_page(repo.as_ref(), &start)?;
+            let next = if page.len() >= PROBLEMS_PAGE_SIZE {
+                page.last().map(|last| last.project_name().to_string())
+            } else {
+                None
+            };
+
+            let page = page
+                .into_iter()
+                .filter(|problem| !known.contains(problem))
+                .collect::<Vec<_>>();
+
+            match next {
+                // A project with more problems than fit on a page cannot be paged through
+                Some(next) if next != start => {
+                    known = page
+                        .iter()
+                        .filter(|problem| **problem.project_name() == next)
+                        .cloned()
+                        .collect();
+                    on_page(page)?;
+                    start = next;
+                }
+                _ => return on_page(page),
+            }
+        }
     }
 
     fn problems_for_maintainer<M: AsRef<str>, R: AsRef<str>>(&self, maintainer: M, repo: R) -> Result<Vec<Problem>> {
@@ -66,6 +221,10 @@ impl Api for RestApi {
         );
         trace!("Request: {}", url);
         let response = self.send_request(url)?;
-        serde_json::from_str(&response).map_err(Error::from)
+        let mut problems: Vec<Problem> = serde_json::from_str(&response)?;
+        problems
+            .iter_mut()
+            .for_each(|p| p.set_repo_if_missing(repo.as_ref()));
+        Ok(problems)
     }
 }
@@ -1,6 +1,6 @@
 use std::ops::Deref;
 
-// list of package downloads
+// name of the project a package belongs to
 #[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Debug, Serialize, Deserialize, new)]
 pub struct EffName(String);
 
@@ -17,6 +17,12 @@ pub struct Package {
     /// package name as shown to the user by Repology
     visiblename: Option<Name>,
 
+    /// name of the project this package belongs to
+    ///
+    /// Not part of the package objects returned by repology, but filled in by the `Api`
+    /// implementations wherever the project is known.
+    effname: Option<EffName>,
+
     /// version
     version: Version,
 
@@ -37,6 +43,10 @@ pub struct Package {
 
     /// list of package downloads
     downloads: Option<Vec<Download>>,
+
+    /// whether the package is marked as potentially vulnerable
+    #[serde(default)]
+    vulnerable: bool,
 }
 
 impl Package {
@@ -69,6 +79,17 @@ impl Package {
             .or_else(|| self.visiblename())
     }
 
+    pub fn effname(&self) -> Option<&EffName> {
+        self.effname.as_ref()
+    }
+
+    /// Set the name of the project this package belongs to, if it is not known yet
+    pub(crate) fn set_effname_if_missing(&mut self, effname: &str) {
+        if self.effname.is_none() {
+            self.effname = Some(EffName::new(effname.to_string()));
+        }
+    }
+
     pub fn version(&self) -> &Version {
         &self.version
     }
@@ -96,4 +117,8 @@ impl Package {
     pub fn downloads(&self) -> Option<&Vec<Download>> {
         self.downloads.as_ref()
     }
+
+    pub fn is_vulnerable(&self) -> bool {
+        self.vulnerable
+    }
 }
@@ -2,6 +2,9 @@ use crate::v1::types::*;
 
 #[derive(Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub struct Problem {
+    #[serde(rename = "repo", default, skip_serializing_if = "Option::is_none")]
+    repo: Option<Repo>,
+
     #[serde(rename = "project_name")]
     project_name: Name,
 
@@ -19,6 +22,17 @@ pub struct Problem {
 }
 
 impl Problem {
+    pub fn repo(&self) -> Option<&Repo> {
+        self.repo.as_ref()
+    }
+
+    /// Set the repository this problem was reported for, if it is not known yet
+    pub(crate) fn set_repo_if_missing(&mut self, repo: &str) {
+        if self.repo.is_none() {
+            self.repo = Some(Repo::new(repo.to_string()));
+        }
+    }
+
     pub fn project_name(&self) -> &Name {
         &self.project_name
     }
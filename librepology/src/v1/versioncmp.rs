@@ -0,0 +1,172 @@
+//! Version comparison
+//!
+//! Repology does not enforce any versioning scheme, so versions can not simply be compared as
+//! strings (`"10.0" < "9.0"`) or as semver (`"1.2.3a"` is not semver). This module implements an
+//! ordering which follows the ideas of repology's own `libversion`:
+//!
+//! * A version is split into numeric and alphabetic components, separators are ignored
+//! * Numeric components are compared numerically
+//! * Known pre-release keywords (`alpha`, `beta`, `rc`, `pre`) and alphabetic components which are
+//!   separated from the previous component are considered pre-releases, thus `1.0alpha1 < 1.0`
+//! * Known post-release keywords (`patch`, `post`, `pl`, `errata`) and letters directly attached
+//!   to a number are considered post-releases, thus `1.0 < 1.0a < 1.0.1`
+//! * Missing components are treated as zero, thus `1.0 == 1.0.0`
+//!
+
+use std::cmp::Ordering;
+
+use crate::v1::types::Version;
+
+const PRE_RELEASE_KEYWORDS: &[&str] = &["alpha", "beta", "rc", "pre"];
+const POST_RELEASE_KEYWORDS: &[&str] = &["patch", "post", "pl", "errata"];
+
+/// One component of a version string
+///
+/// The order of the variants is the order of the components, so the derived `Ord` implementation
+/// is the comparison of the components.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Debug)]
+enum Component {
+    PreRelease(String),
+    Zero,
+    PostRelease(String),
+
+    /// A number, without leading zeros
+    ///
+    /// The number of digits is stored before the digits, so that the comparison does not need to
+    /// parse the number (which could overflow).
+    Number {
+        len: usize,
+        digits: String,
+    },
+}
+
+fn components(version: &str) -> Vec<Component> {
+    let mut result = Vec::new();
+    let mut chars = version.chars().peekable();
+    let mut attached = false;
+
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_digit() {
+            let mut digits = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                digits.push(c);
+                chars.next();
+            }
+
+            let digits = digits.trim_start_matches('0').to_string();
+            if digits.is_empty() {
+                result.push(Component::Zero);
+            } else {
+                result.push(Component::Number {
+                    len: digits.len(),
+                    digits,
+                });
+            }
+            attached = true;
+        } else if c.is_alphabetic() {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_alphabetic()) {
+                word.extend(c.to_lowercase());
+                chars.next();
+            }
+
+            if PRE_RELEASE_KEYWORDS.contains(&word.as_str()) {
+                result.push(Component::PreRelease(word));
+            } else if POST_RELEASE_KEYWORDS.contains(&word.as_str()) || attached {
+                result.push(Component::PostRelease(word));
+            } else {
+                result.push(Component::PreRelease(word));
+            }
+            attached = false;
+        } else {
+            chars.next();
+            attached = false;
+        }
+    }
+
+    result
+}
+
+/// Compare two versions
+pub fn compare(a: &Version, b: &Version) -> Ordering {
+    compare_str(a, b)
+}
+
+/// Compare two version strings
+pub fn compare_str(a: &str, b: &str) -> Ordering {
+    let a = components(a);
+    let b = components(b);
+    let len = std::cmp::max(a.len(), b.len());
+
+    (0..len)
+        .map(|i| {
+            let left = a.get(i).unwrap_or(&Component::Zero);
+            let right = b.get(i).unwrap_or(&Component::Zero);
+            left.cmp(right)
+        })
+        .find(|ord| *ord != Ordering::Equal)
+        .unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::compare_str;
+
+    fn assert_order(a: &str, b: &str, ord: Ordering) {
+        assert_eq!(compare_str(a, b), ord, "{} vs {}", a, b);
+        assert_eq!(compare_str(b, a), ord.reverse(), "{} vs {}", b, a);
+    }
+
+    #[test]
+    fn test_numeric_components() {
+        assert_order("1.0", "1.0", Ordering::Equal);
+        assert_order("9.0", "10.0", Ordering::Less);
+        assert_order("1.2.3", "1.2.4", Ordering::Less);
+        assert_order("1.123456789012345678901234567890", "1.9", Ordering::Greater);
+    }
+
+    #[test]
+    fn test_leading_zeros() {
+        assert_order("1.01", "1.1", Ordering::Equal);
+        assert_order("007", "7", Ordering::Equal);
+        assert_order("1.010", "1.9", Ordering::Greater);
+        assert_order("1.00", "1.0", Ordering::Equal);
+    }
+
+    #[test]
+    fn test_differing_component_counts() {
+        assert_order("1.0", "1.0.0", Ordering::Equal);
+        assert_order("1.0", "1.0.1", Ordering::Less);
+        assert_order("1.2", "1.1.9", Ordering::Greater);
+    }
+
+    #[test]
+    fn test_pre_releases() {
+        assert_order("1.0alpha1", "1.0", Ordering::Less);
+        assert_order("1.0-rc1", "1.0", Ordering::Less);
+        assert_order("1.0alpha", "1.0beta", Ordering::Less);
+        assert_order("1.0beta2", "1.0rc1", Ordering::Less);
+        assert_order("1.0rc1", "1.0rc2", Ordering::Less);
+        assert_order("1.0RC1", "1.0rc1", Ordering::Equal);
+        assert_order("1.0.dev", "1.0", Ordering::Less);
+        assert_order("0.9", "1.0alpha1", Ordering::Less);
+    }
+
+    #[test]
+    fn test_post_releases() {
+        assert_order("1.0", "1.0patch1", Ordering::Less);
+        assert_order("1.0", "1.0.post1", Ordering::Less);
+        assert_order("1.0pl2", "1.0.1", Ordering::Less);
+        assert_order("1.0alpha1", "1.0patch1", Ordering::Less);
+    }
+
+    #[test]
+    fn test_letter_suffixes() {
+        assert_order("1.0", "1.0a", Ordering::Less);
+        assert_order("1.0a", "1.0b", Ordering::Less);
+        assert_order("1.0b", "1.0.1", Ordering::Less);
+        assert_order("1.1.1w", "1.1.1", Ordering::Greater);
+    }
+}
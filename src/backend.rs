@@ -1,7 +1,11 @@
+use std::collections::BTreeMap;
+
 use clap::ArgMatches;
 
 use librepology::v1::api::Api;
 use librepology::v1::buffer::BufferApi;
+use librepology::v1::envelope::Envelope;
+use librepology::v1::error::RepologyError;
 use librepology::v1::error::Result;
 use librepology::v1::restapi::RestApi;
 use librepology::v1::types::*;
@@ -26,6 +30,13 @@ impl Api for Backend {
         }
     }
 
+    fn projects_in_repo<R: AsRef<str>>(&self, repo: R) -> Result<BTreeMap<Name, Vec<Package>>> {
+        match self {
+            Backend::Buffer(inner) => inner.projects_in_repo(repo),
+            Backend::RepologyOrg(inner) => inner.projects_in_repo(repo),
+        }
+    }
+
     fn problems_for_repo<R: AsRef<str>>(&self, repo: R) -> Result<Vec<Problem>> {
         match self {
             Backend::Buffer(inner) => inner.problems_for_repo(repo),
@@ -33,6 +44,18 @@ impl Api for Backend {
         }
     }
 
+    fn problems_for_repo_paged<R, F, E>(&self, repo: R, on_page: F) -> std::result::Result<(), E>
+    where
+        R: AsRef<str>,
+        F: FnMut(Vec<Problem>) -> std::result::Result<(), E>,
+        E: From<RepologyError>,
+    {
+        match self {
+            Backend::Buffer(inner) => inner.problems_for_repo_paged(repo, on_page),
+            Backend::RepologyOrg(inner) => inner.problems_for_repo_paged(repo, on_page),
+        }
+    }
+
     fn problems_for_maintainer<M: AsRef<str>, R: AsRef<str>>(
         &self,
         maintainer: M,
@@ -45,6 +68,16 @@ impl Api for Backend {
     }
 }
 
+impl Backend {
+    /// The envelope of the input without its data, if the input is enveloped data from stdin
+    pub fn input_envelope(&self) -> Result<Option<Envelope<()>>> {
+        match self {
+            Backend::Buffer(inner) => inner.envelope(),
+            Backend::RepologyOrg(_) => Ok(None),
+        }
+    }
+}
+
 pub fn new_backend(app: &ArgMatches, config: &Configuration) -> anyhow::Result<Backend> {
     if app.get_flag("input_stdin") {
         trace!("Building new STDIN backend");
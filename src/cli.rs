@@ -1,5 +1,43 @@
 use clap::{Arg, Command};
 
+/// Arguments for overriding the repository rules from the configuration
+fn repository_rule_args() -> [Arg; 2] {
+    [
+        Arg::new("allow-repo")
+            .long("allow-repo")
+            .value_name("PATTERN")
+            .num_args(1)
+            .action(clap::ArgAction::Append)
+            .help("Allow repositories matching PATTERN (glob, regex if enclosed in slashes, or repository group or alias). Rules from the commandline replace the rules from the configuration, the first matching rule decides"),
+        Arg::new("deny-repo")
+            .long("deny-repo")
+            .value_name("PATTERN")
+            .num_args(1)
+            .action(clap::ArgAction::Append)
+            .help("Deny repositories matching PATTERN (glob, regex if enclosed in slashes, or repository group or alias). Rules from the commandline replace the rules from the configuration, the first matching rule decides"),
+    ]
+}
+
+/// The names of the available output formats
+fn output_formats() -> Vec<&'static str> {
+    let mut formats = vec!["table", "matrix", "json", "ndjson", "lines", "markdown"];
+    if cfg!(feature = "csv_output") {
+        formats.extend(["csv", "tsv"]);
+    }
+    if cfg!(feature = "html_output") {
+        formats.push("html");
+    }
+    formats
+}
+
+/// Argument for only showing packages which repology marks as vulnerable
+fn vulnerable_only_arg() -> Arg {
+    Arg::new("vulnerable-only")
+        .long("vulnerable-only")
+        .action(clap::ArgAction::SetTrue)
+        .help("Only show packages which repology marks as potentially vulnerable")
+}
+
 pub fn build_cli() -> Command {
     Command::new("repolocli")
         .version("0.1")
@@ -10,9 +48,23 @@ pub fn build_cli() -> Command {
             .long("config")
             .value_name("PATH")
             .required(false)
-            .num_args(..)
-            .help("Override default configuration file path")
+            .num_args(1)
+            .help("Override default configuration file path (replaces the user configuration, system and project configuration are still read)")
+        )
 
+        .arg(Arg::new("profile")
+            .long("profile")
+            .value_name("NAME")
+            .num_args(1)
+            .help("Apply the [profile.NAME] sections of the configuration files. Default: $REPOLOCLI_PROFILE")
+        )
+
+        .arg(Arg::new("repology-url")
+            .long("repology-url")
+            .value_name("URL")
+            .num_args(1)
+            .value_parser(url::Url::parse)
+            .help("Override the URL of the repology instance from the configuration")
         )
 
         .arg(Arg::new("verbose")
@@ -34,10 +86,73 @@ pub fn build_cli() -> Command {
             .short('o')
             .required(false)
             .num_args(1)
-            .value_parser(["table", "json", "lines"])
+            .value_parser(output_formats())
             .default_value("lines")
             .help("Output format")
         )
+        .arg(Arg::new("template")
+            .long("template")
+            .value_name("PATH")
+            .num_args(1)
+            .help("Render the html output with the handlebars template at PATH instead of the built-in templates. The stylesheet can be embedded with {{> style}}")
+        )
+
+        .arg(Arg::new("format")
+            .long("format")
+            .value_name("FORMAT")
+            .num_args(1)
+            .help("Print each package or problem of the lines output with FORMAT, for example '{repo}\\t{version:>10}\\t{status|no status}'. Placeholders are {FIELD[/SEPARATOR][:[ALIGN]WIDTH][|DEFAULT]}")
+        )
+        .arg(Arg::new("columns")
+            .long("columns")
+            .value_name("COLUMNS")
+            .num_args(1)
+            .action(clap::ArgAction::Append)
+            .value_delimiter(',')
+            .conflicts_with("format")
+            .help("Show these fields of packages or problems, in this order, in the table, lines, csv, tsv and markdown outputs, for example: name,repo,version,maintainers,licenses")
+        )
+        .arg(Arg::new("color")
+            .long("color")
+            .value_name("WHEN")
+            .num_args(1)
+            .value_parser(["auto", "always", "never"])
+            .default_value("auto")
+            .help("Color the lines and table outputs by package status. 'auto' colors if stdout is a terminal and NO_COLOR is not set")
+        )
+        .arg(Arg::new("no-truncate")
+            .long("no-truncate")
+            .action(clap::ArgAction::SetTrue)
+            .help("Do not truncate or wrap URLs, summaries and other long columns of the lines and table outputs to fit into the terminal")
+        )
+        .arg(Arg::new("envelope")
+            .long("envelope")
+            .action(clap::ArgAction::SetTrue)
+            .help("Wrap the json output in an envelope with the schema version, the query, the fetch time and the source URL. Enveloped output can be read again with --stdin, the fetch time and source of enveloped input are kept")
+        )
+
+        .arg(Arg::new("fail-on")
+            .long("fail-on")
+            .value_name("CONDITION")
+            .num_args(1)
+            .action(clap::ArgAction::Append)
+            .value_delimiter(',')
+            .value_parser(["outdated", "problems", "vulnerable"])
+            .help("Exit with code 10 if the query succeeded, but the results contain outdated packages, problems or vulnerable packages. 'outdated' and 'vulnerable' apply to the project and report subcommands, 'problems' to the problems subcommand")
+        )
+
+        .arg(Arg::new("filter")
+            .long("filter")
+            .value_name("EXPRESSION")
+            .num_args(1)
+            .help("Only show packages and problems matching EXPRESSION, for example: status == outdated && repo ~ \"debian_*\"")
+        )
+
+        .arg(Arg::new("no-ignore")
+            .long("no-ignore")
+            .action(clap::ArgAction::SetTrue)
+            .help("Do not hide the packages and problems acknowledged in the ignore_file from the configuration")
+        )
 
         .arg(Arg::new("input_stdin")
             .long("stdin")
@@ -47,11 +162,18 @@ pub fn build_cli() -> Command {
         )
 
         .subcommand(Command::new("project")
+            .args(repository_rule_args())
             .arg(Arg::new("project_name")
                 .index(1)
-                .required(true)
+                .required(false)
+                .num_args(1..)
+                .help("Query data about one or more projects. Local names are translated with the name_mapping from the configuration. If no project and no file is given, the project names are read from stdin, one per line")
+            )
+            .arg(Arg::new("from-file")
+                .long("from-file")
+                .value_name("PATH")
                 .num_args(1)
-                .help("Query data about a project")
+                .help("Read project names from a file, one per line. Empty lines and lines starting with '#' are ignored")
             )
 
             .arg(Arg::new("sort-version")
@@ -69,26 +191,35 @@ pub fn build_cli() -> Command {
             .arg(Arg::new("latest")
                 .long("latest")
                 .action(clap::ArgAction::SetTrue)
-                .help("Try to find the lastest version (version is string-compared if not used with --semver)")
+                .help("Try to find the lastest version")
                 .conflicts_with("sort-version")
                 .conflicts_with("sort-repo")
             )
-            .arg(Arg::new("semver")
-                .long("semver")
+            .arg(Arg::new("relative-to")
+                .long("relative-to")
+                .value_name("REPO")
+                .num_args(1)
+                .conflicts_with("latest")
+                .help("Show whether the packages in other repositories are older, the same or newer than in REPO (a repository, alias or group, for groups the newest version in the group is the reference)")
+            )
+            .arg(vulnerable_only_arg())
+            .arg(Arg::new("ahead-only")
+                .long("ahead-only")
                 .action(clap::ArgAction::SetTrue)
-                .requires("latest")
-                .help("Try to find latest version using semver. If semver could not be parsed, equality is assumed, which might yield bogus results.")
+                .requires("relative-to")
+                .help("Only show repositories which have a newer version than the reference repository")
             )
         )
 
         .subcommand(Command::new("problems")
+            .args(repository_rule_args())
             .arg(Arg::new("repo")
                 .short('r')
                 .long("repo")
                 .alias("repository")
                 .required(true)
                 .num_args(1)
-                .help("The repository to get problems for (required)")
+                .help("The repository, repository alias or group to get problems for (required)")
             )
 
             .arg(Arg::new("maintainer")
@@ -115,7 +246,92 @@ pub fn build_cli() -> Command {
             )
         )
 
+        .subcommand(Command::new("diff-repos")
+            .about("Compare the projects packaged in two repositories")
+            .args(repository_rule_args())
+            .arg(Arg::new("repo_a")
+                .index(1)
+                .required(true)
+                .num_args(1)
+                .help("The first repository (or repository alias)")
+            )
+            .arg(Arg::new("repo_b")
+                .index(2)
+                .required(true)
+                .num_args(1)
+                .help("The second repository (or repository alias)")
+            )
+            .arg(Arg::new("only-differing")
+                .long("only-differing")
+                .action(clap::ArgAction::SetTrue)
+                .help("Do not list projects which have the same version in both repositories")
+            )
+        )
+
+        .subcommand(Command::new("report")
+            .about("Print a freshness report for all projects on the watchlist from the configuration")
+            .args(repository_rule_args())
+            .arg(vulnerable_only_arg())
+        )
+
+        .subcommand(Command::new("watch")
+            .about("Print what changed for the projects on the watchlist since the previous run")
+            .args(repository_rule_args())
+            .arg(Arg::new("state")
+                .long("state")
+                .value_name("PATH")
+                .num_args(1)
+                .help("Path of the state file (default: repolocli/watch-state.json in the XDG data directory)")
+            )
+            .arg(Arg::new("interval")
+                .long("interval")
+                .value_name("SECONDS")
+                .num_args(1)
+                .value_parser(clap::value_parser!(u64))
+                .help("Do not exit, but check for changes again every SECONDS seconds")
+            )
+        )
+
+        .subcommand(Command::new("check")
+            .about("Check the packages and problems of projects against the rules of a freshness policy")
+            .args(repository_rule_args())
+            .arg(Arg::new("policy")
+                .long("policy")
+                .value_name("PATH")
+                .required(true)
+                .num_args(1)
+                .help("Path of the policy file with the rules to check")
+            )
+        )
+
+        .subcommand(Command::new("config")
+            .about("Inspect, validate and scaffold the configuration")
+            .subcommand_required(true)
+            .subcommand(Command::new("show")
+                .about("Print the effective configuration and where each value comes from")
+            )
+            .subcommand(Command::new("check")
+                .about("Validate the configuration, including whether repology knows the configured repositories")
+                .arg(Arg::new("no-network")
+                    .long("no-network")
+                    .action(clap::ArgAction::SetTrue)
+                    .help("Do not query repology, only check the configuration files")
+                )
+            )
+            .subcommand(Command::new("init")
+                .about("Write a commented starter configuration to the XDG config directory")
+                .arg(Arg::new("force")
+                    .long("force")
+                    .action(clap::ArgAction::SetTrue)
+                    .help("Overwrite an existing configuration file")
+                )
+            )
+        )
+
         .after_help(r#"
+        Exit codes: 0 = success, 1 = unspecified error, 2 = usage error, 3 = network error,
+        4 = API error, 5 = parse error, 10 = results matched --fail-on or the policy was violated.
+
         repolocli can read data from stdin, if you want to postprocess repology.org data you already
         fetched from repology.org/api/v1 via curl (or some other method).
         In this case, repolocli is only a easier-to-use 'jq' (if you don't know jq, look it up NOW!).
@@ -1,22 +1,272 @@
+//! Configuration
+//!
+//! The configuration is merged from the following layers, where later layers override earlier
+//! ones:
+//!
+//! 1. Built-in defaults
+//! 2. System configuration: `repolocli.toml` in the XDG config directories (`$XDG_CONFIG_DIRS`,
+//!    `/etc/xdg` by default)
+//! 3. User configuration: `$XDG_CONFIG_HOME/repolocli.toml`, or the file passed with `--config`
+//! 4. Project configuration: the first `.repolocli.toml` in the current directory or one of its
+//!    parents
+//! 5. Environment variables: `REPOLOCLI_REPOLOGY_URL`, `REPOLOCLI_REPOSITORY_RULES` (rules
+//!    separated by `;`), `REPOLOCLI_NAME_MAPPING` and `REPOLOCLI_IGNORE_FILE`
+//! 6. Commandline flags: `--repology-url`
+//!
+//! Lists are replaced by later layers, the watchlist, repository groups, repository aliases, line
+//! formats, columns and colors are merged by name.
+//!
+//! Repository groups (`[repository_groups]`, a name for a list of repositories) and aliases
+//! (`[repository_aliases]`, a short name for one repository) can be used wherever a repository is
+//! accepted. Members of groups can be aliases.
+//!
+//! Each file can contain named profiles in `[profile.NAME]` tables, with the same keys as the top
+//! level. The profile selected with `--profile` (or `REPOLOCLI_PROFILE`) is applied right after
+//! the top level values of each file.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Error;
+use anyhow::Result;
+use clap::ArgMatches;
+use librepology::v1::types::Repo;
 use url::Url;
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::exitcode::UsageError;
+
+const CONFIG_FILE_NAME: &str = "repolocli.toml";
+const PROJECT_CONFIG_FILE_NAME: &str = ".repolocli.toml";
+const DEFAULT_REPOLOGY_URL: &str = "https://repology.org";
+
+#[derive(Debug, Serialize)]
 pub struct Configuration {
     #[serde(rename = "repology_url")]
     repology_url: Url,
 
+    /// Ordered repository rules, see `crate::filter`
+    #[serde(rename = "repository_rules")]
+    repository_rules: Vec<String>,
+
     #[serde(rename = "whitelist")]
     whitelist: Vec<String>,
 
     #[serde(rename = "blacklist")]
     blacklist: Vec<String>,
+
+    #[serde(rename = "watchlist")]
+    watchlist: BTreeMap<String, WatchlistEntry>,
+
+    #[serde(rename = "repository_groups")]
+    repository_groups: BTreeMap<String, Vec<String>>,
+
+    #[serde(rename = "repository_aliases")]
+    repository_aliases: BTreeMap<String, String>,
+
+    /// Path of the file mapping local names to repology project names, see `crate::mapping`
+    #[serde(rename = "name_mapping", skip_serializing_if = "Option::is_none")]
+    name_mapping: Option<PathBuf>,
+
+    /// Path of the file with acknowledged packages and problems, see `crate::ignore`
+    #[serde(rename = "ignore_file", skip_serializing_if = "Option::is_none")]
+    ignore_file: Option<PathBuf>,
+
+    /// Line formats of the `lines` output by subcommand, see `crate::format`
+    #[serde(rename = "formats")]
+    formats: BTreeMap<String, String>,
+
+    /// Columns of the packages and problems by subcommand, see `crate::format`
+    #[serde(rename = "columns")]
+    columns: BTreeMap<String, Vec<String>>,
+
+    /// Colors of the terminal outputs by status, see `crate::frontend::color`
+    #[serde(rename = "colors")]
+    colors: BTreeMap<String, String>,
+
+    /// Where the values come from, by key (`watchlist.PROJECT` for watchlist entries)
+    #[serde(skip)]
+    sources: BTreeMap<String, String>,
+}
+
+impl Default for Configuration {
+    fn default() -> Self {
+        Configuration {
+            repology_url: Url::parse(DEFAULT_REPOLOGY_URL).unwrap(), // safe, it is a valid URL
+            repository_rules: Vec::new(),
+            whitelist: Vec::new(),
+            blacklist: Vec::new(),
+            watchlist: BTreeMap::new(),
+            repository_groups: BTreeMap::new(),
+            repository_aliases: BTreeMap::new(),
+            name_mapping: None,
+            ignore_file: None,
+            formats: BTreeMap::new(),
+            columns: BTreeMap::new(),
+            colors: BTreeMap::new(),
+            sources: BTreeMap::new(),
+        }
+    }
 }
 
 impl Configuration {
+    /// Load the configuration by merging all layers
+    pub fn load(app: &ArgMatches) -> Result<Self> {
+        let profile = app
+            .get_one::<String>("profile")
+            .cloned()
+            .or_else(|| std::env::var("REPOLOCLI_PROFILE").ok());
+        trace!("profile = {:?}", profile);
+
+        let mut config = Configuration::default();
+        let mut profile_found = false;
+
+        for path in config_files(app)? {
+            let FileLayer {
+                layer,
+                mut profiles,
+            } = FileLayer::read(&path)?;
+            config.apply(layer, path.display().to_string());
+
+            if let Some((name, layer)) = profile
+                .as_ref()
+                .and_then(|p| profiles.remove(p).map(|layer| (p, layer)))
+            {
+                debug!("Applying profile from {}", path.display());
+                profile_found = true;
+                config.apply(layer, format!("{} (profile {})", path.display(), name));
+            }
+        }
+
+        if let Some(profile) = profile.filter(|_| !profile_found) {
+            return Err(Error::from(UsageError::new(format!(
+                "Profile '{}' not found in any configuration file",
+                profile
+            ))));
+        }
+
+        config.apply(Layer::from_env()?, String::from("environment"));
+        config.apply(Layer::from_matches(app), String::from("commandline"));
+        config.resolve_watchlist();
+        Ok(config)
+    }
+
+    fn apply(&mut self, layer: Layer, source: String) {
+        let mut keys = Vec::new();
+
+        if let Some(url) = layer.repology_url {
+            self.repology_url = url;
+            keys.push(String::from("repology_url"));
+        }
+        if let Some(rules) = layer.repository_rules {
+            self.repository_rules = rules;
+            keys.push(String::from("repository_rules"));
+        }
+        if let Some(whitelist) = layer.whitelist {
+            self.whitelist = whitelist;
+            keys.push(String::from("whitelist"));
+        }
+        if let Some(blacklist) = layer.blacklist {
+            self.blacklist = blacklist;
+            keys.push(String::from("blacklist"));
+        }
+        if let Some(name_mapping) = layer.name_mapping {
+            self.name_mapping = Some(name_mapping);
+            keys.push(String::from("name_mapping"));
+        }
+        if let Some(ignore_file) = layer.ignore_file {
+            self.ignore_file = Some(ignore_file);
+            keys.push(String::from("ignore_file"));
+        }
+        keys.extend(
+            layer
+                .watchlist
+                .keys()
+                .map(|project| format!("watchlist.{}", project)),
+        );
+        self.watchlist.extend(layer.watchlist);
+        keys.extend(
+            layer
+                .repository_groups
+                .keys()
+                .map(|group| format!("repository_groups.{}", group)),
+        );
+        self.repository_groups.extend(layer.repository_groups);
+        keys.extend(
+            layer
+                .repository_aliases
+                .keys()
+                .map(|alias| format!("repository_aliases.{}", alias)),
+        );
+        self.repository_aliases.extend(layer.repository_aliases);
+        keys.extend(
+            layer
+                .formats
+                .keys()
+                .map(|command| format!("formats.{}", command)),
+        );
+        self.formats.extend(layer.formats);
+        keys.extend(
+            layer
+                .columns
+                .keys()
+                .map(|command| format!("columns.{}", command)),
+        );
+        self.columns.extend(layer.columns);
+        keys.extend(
+            layer
+                .colors
+                .keys()
+                .map(|status| format!("colors.{}", status)),
+        );
+        self.colors.extend(layer.colors);
+
+        for key in keys {
+            self.sources.insert(key, source.clone());
+        }
+    }
+
+    /// Replace groups and aliases in the repositories of the watchlist entries
+    fn resolve_watchlist(&mut self) {
+        let resolved = self
+            .watchlist
+            .iter()
+            .map(|(project, entry)| {
+                let repositories = entry
+                    .repositories
+                    .iter()
+                    .flat_map(|name| self.resolve_repository(name))
+                    .map(|repo| repo.to_string())
+                    .collect::<Vec<_>>();
+                (project.clone(), repositories)
+            })
+            .collect::<Vec<_>>();
+
+        for (project, repositories) in resolved {
+            if let Some(entry) = self.watchlist.get_mut(&project) {
+                entry.repositories = repositories;
+            }
+        }
+    }
+
+    /// Where the value for `key` comes from: a file path, "environment", "commandline" or
+    /// "default"
+    pub fn source(&self, key: &str) -> &str {
+        self.sources
+            .get(key)
+            .map(String::as_str)
+            .unwrap_or("default")
+    }
+
     pub fn repology_url(&self) -> &Url {
         &self.repology_url
     }
 
+    pub fn repository_rules(&self) -> &Vec<String> {
+        &self.repository_rules
+    }
+
     pub fn whitelist(&self) -> &Vec<String> {
         &self.whitelist
     }
@@ -24,4 +274,260 @@ impl Configuration {
     pub fn blacklist(&self) -> &Vec<String> {
         &self.blacklist
     }
+
+    pub fn watchlist(&self) -> &BTreeMap<String, WatchlistEntry> {
+        &self.watchlist
+    }
+
+    pub fn repository_groups(&self) -> &BTreeMap<String, Vec<String>> {
+        &self.repository_groups
+    }
+
+    pub fn repository_aliases(&self) -> &BTreeMap<String, String> {
+        &self.repository_aliases
+    }
+
+    pub fn name_mapping(&self) -> Option<&PathBuf> {
+        self.name_mapping.as_ref()
+    }
+
+    pub fn ignore_file(&self) -> Option<&PathBuf> {
+        self.ignore_file.as_ref()
+    }
+
+    pub fn formats(&self) -> &BTreeMap<String, String> {
+        &self.formats
+    }
+
+    pub fn columns(&self) -> &BTreeMap<String, Vec<String>> {
+        &self.columns
+    }
+
+    pub fn colors(&self) -> &BTreeMap<String, String> {
+        &self.colors
+    }
+
+    /// Resolve a repository name, group or alias to the repositories it stands for
+    pub fn resolve_repository(&self, name: &str) -> Vec<Repo> {
+        match self.repository_groups.get(name) {
+            Some(members) => members
+                .iter()
+                .map(|member| self.resolve_alias(member))
+                .collect(),
+            None => vec![self.resolve_alias(name)],
+        }
+    }
+
+    /// Resolve a repository name or alias to exactly one repository
+    ///
+    /// Fails for groups, for places where only one repository makes sense.
+    pub fn resolve_single_repository(&self, name: &str) -> Result<Repo> {
+        if self.repository_groups.contains_key(name) {
+            return Err(Error::from(UsageError::new(format!(
+                "'{}' is a repository group, but only a single repository is accepted here",
+                name
+            ))));
+        }
+
+        Ok(self.resolve_alias(name))
+    }
+
+    fn resolve_alias(&self, name: &str) -> Repo {
+        let name = self
+            .repository_aliases
+            .get(name)
+            .map(String::as_str)
+            .unwrap_or(name);
+        Repo::new(name.to_string())
+    }
+}
+
+/// Find the configuration files which exist, in the order in which they are applied
+pub fn config_files(app: &ArgMatches) -> Result<Vec<PathBuf>> {
+    let xdg = xdg::BaseDirectories::new();
+
+    // XDG lists the most important directory first
+    let mut files = xdg
+        .get_config_dirs()
+        .into_iter()
+        .rev()
+        .map(|dir| dir.join(CONFIG_FILE_NAME))
+        .filter(|path| path.exists())
+        .collect::<Vec<_>>();
+
+    match app.get_one::<String>("config").map(PathBuf::from) {
+        Some(path) if !path.exists() => {
+            return Err(Error::from(UsageError::new(format!(
+                "Configuration file {} does not exist",
+                path.display()
+            ))))
+        }
+        Some(path) => files.push(path),
+        None => files.extend(
+            xdg.get_config_home()
+                .map(|dir| dir.join(CONFIG_FILE_NAME))
+                .filter(|path| path.exists()),
+        ),
+    }
+
+    let cwd = std::env::current_dir().context("Finding current directory")?;
+    files.extend(
+        cwd.ancestors()
+            .map(|dir| dir.join(PROJECT_CONFIG_FILE_NAME))
+            .find(|path| path.exists()),
+    );
+
+    debug!("Configuration files: {:?}", files);
+    Ok(files)
+}
+
+/// A partial configuration, as read from one of the layers
+#[derive(Debug, Default, Deserialize)]
+struct Layer {
+    #[serde(rename = "repology_url")]
+    repology_url: Option<Url>,
+
+    #[serde(rename = "repository_rules")]
+    repository_rules: Option<Vec<String>>,
+
+    #[serde(rename = "whitelist")]
+    whitelist: Option<Vec<String>>,
+
+    #[serde(rename = "blacklist")]
+    blacklist: Option<Vec<String>>,
+
+    #[serde(rename = "watchlist", default)]
+    watchlist: BTreeMap<String, WatchlistEntry>,
+
+    #[serde(rename = "repository_groups", default)]
+    repository_groups: BTreeMap<String, Vec<String>>,
+
+    #[serde(rename = "repository_aliases", default)]
+    repository_aliases: BTreeMap<String, String>,
+
+    /// Relative paths are relative to the directory of the configuration file
+    #[serde(rename = "name_mapping")]
+    name_mapping: Option<PathBuf>,
+
+    /// Relative paths are relative to the directory of the configuration file
+    #[serde(rename = "ignore_file")]
+    ignore_file: Option<PathBuf>,
+
+    #[serde(rename = "formats", default)]
+    formats: BTreeMap<String, String>,
+
+    #[serde(rename = "columns", default)]
+    columns: BTreeMap<String, Vec<String>>,
+
+    #[serde(rename = "colors", default)]
+    colors: BTreeMap<String, String>,
+}
+
+impl Layer {
+    fn from_env() -> Result<Self> {
+        let repology_url = std::env::var("REPOLOCLI_REPOLOGY_URL")
+            .ok()
+            .map(|url| {
+                Url::parse(&url).map_err(|e| {
+                    Error::from(UsageError::new(format!(
+                        "Invalid URL in REPOLOCLI_REPOLOGY_URL: {}",
+                        e
+                    )))
+                })
+            })
+            .transpose()?;
+
+        let repository_rules = std::env::var("REPOLOCLI_REPOSITORY_RULES")
+            .ok()
+            .map(|rules| {
+                rules
+                    .split(';')
+                    .map(str::trim)
+                    .filter(|rule| !rule.is_empty())
+                    .map(String::from)
+                    .collect()
+            });
+
+        let name_mapping = std::env::var("REPOLOCLI_NAME_MAPPING")
+            .ok()
+            .map(PathBuf::from);
+
+        let ignore_file = std::env::var("REPOLOCLI_IGNORE_FILE")
+            .ok()
+            .map(PathBuf::from);
+
+        Ok(Layer {
+            repology_url,
+            repository_rules,
+            name_mapping,
+            ignore_file,
+            ..Layer::default()
+        })
+    }
+
+    fn from_matches(app: &ArgMatches) -> Self {
+        Layer {
+            repology_url: app.get_one::<Url>("repology-url").cloned(),
+            ..Layer::default()
+        }
+    }
+}
+
+/// The content of a configuration file
+#[derive(Debug, Default, Deserialize)]
+struct FileLayer {
+    #[serde(flatten)]
+    layer: Layer,
+
+    #[serde(rename = "profile", default)]
+    profiles: BTreeMap<String, Layer>,
+}
+
+impl FileLayer {
+    fn read(path: &Path) -> Result<Self> {
+        debug!("Parsing configuration from file: {}", path.display());
+        let buffer = std::fs::read_to_string(path)
+            .with_context(|| format!("Reading configuration from {}", path.display()))?;
+
+        let mut file: FileLayer = toml::de::from_str(&buffer)
+            .map_err(Error::from)
+            .with_context(|| format!("Parsing configuration from {}", path.display()))?;
+
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        std::iter::once(&mut file.layer)
+            .chain(file.profiles.values_mut())
+            .flat_map(|layer| {
+                layer
+                    .name_mapping
+                    .as_mut()
+                    .into_iter()
+                    .chain(layer.ignore_file.as_mut())
+            })
+            .filter(|path| path.is_relative())
+            .for_each(|path| *path = dir.join(&path));
+
+        Ok(file)
+    }
+}
+
+/// A project on the watchlist
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WatchlistEntry {
+    /// The version of the project that is used locally, if any
+    #[serde(rename = "version", default)]
+    version: Option<String>,
+
+    /// The repositories which are of interest for this project
+    #[serde(rename = "repositories", default)]
+    repositories: Vec<String>,
+}
+
+impl WatchlistEntry {
+    pub fn version(&self) -> Option<&String> {
+        self.version.as_ref()
+    }
+
+    pub fn repositories(&self) -> &Vec<String> {
+        &self.repositories
+    }
 }
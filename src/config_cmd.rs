@@ -0,0 +1,462 @@
+//! Implementation of the 'config' subcommand
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::ops::Deref;
+use std::path::Path;
+
+use anyhow::Context;
+use anyhow::Error;
+use anyhow::Result;
+use clap::ArgMatches;
+use librepology::v1::api::Api;
+use librepology::v1::restapi::RestApi;
+use serde::Serialize;
+
+use crate::config::Configuration;
+use crate::exitcode::UsageError;
+use crate::filter::RepoFilter;
+use crate::format::Columns;
+use crate::format::LineFormat;
+use crate::frontend::color::Theme;
+use crate::ignore::IgnoreList;
+use crate::mapping::NameMapping;
+
+const KNOWN_KEYS: &[&str] = &[
+    "repology_url",
+    "repository_rules",
+    "whitelist",
+    "blacklist",
+    "watchlist",
+    "repository_groups",
+    "repository_aliases",
+    "name_mapping",
+    "ignore_file",
+    "formats",
+    "columns",
+    "colors",
+];
+const KNOWN_WATCHLIST_KEYS: &[&str] = &["version", "repositories"];
+
+/// Starter configuration written by 'config init'
+const STARTER_CONFIG: &str = r#"# repolocli configuration
+#
+# This file is merged with the system configuration ($XDG_CONFIG_DIRS/repolocli.toml), a
+# project-local .repolocli.toml and REPOLOCLI_* environment variables.
+# Run "repolocli config show" to see the effective configuration.
+
+# The repology instance to query
+#repology_url = "https://repology.org"
+
+# Ordered rules deciding which repositories repolocli prints information for.
+# Each rule is "allow PATTERN" or "deny PATTERN", where PATTERN is a glob ("debian_*") or, if
+# enclosed in slashes, a regular expression ("/^fedora_\\d+$/").
+# The first matching rule decides. If no rule matches, a repository is denied if there is at least
+# one "allow" rule, and allowed otherwise.
+#repository_rules = [
+#    "deny scientific_*",
+#    "allow debian_*",
+#]
+
+# File mapping local package names to repology project names, relative to this file.
+# It contains exact entries ([names], "libfoo-dev" = "foo") and rewrite rules, for example:
+#   [[rules]]
+#   strip_prefix = "python3-"
+#   prefix = "python:"
+#name_mapping = "name-mapping.toml"
+
+# File with acknowledged outdated packages and problems, relative to this file. Matching packages
+# and problems are hidden and do not trigger --fail-on, until the entry expires:
+#   [[ignore]]
+#   project = "curl"
+#   repo = "debian_12"
+#   version = "7.88.1"
+#   expires = 2026-12-31
+#   reason = "Pinned until the next stable release"
+#ignore_file = "ignore.toml"
+
+# Line formats of the "lines" output, per subcommand (project, problems). Placeholders are
+# {FIELD[/SEPARATOR][:[ALIGN]WIDTH][|DEFAULT]}, for example:
+#[formats]
+#project = "{repo:<15} {version:>10} {status|no status} {maintainers/ }"
+#problems = "{project}\t{type}"
+
+# Columns of the table, lines, csv, tsv and markdown outputs, per subcommand (project, problems).
+#[columns]
+#project = ["name", "repo", "version", "maintainers", "licenses"]
+
+# Colors of the lines and table outputs by package status, and for vulnerable packages. Colors are
+# black, red, green, yellow, blue, magenta, cyan, white, their bright_ variants, or "none".
+#[colors]
+#outdated = "bright_red"
+#vulnerable = "magenta"
+
+# Named groups of repositories and short aliases, usable wherever a repository is accepted.
+#[repository_groups]
+#debian = ["debian_11", "debian_12", "debian_unstable"]
+#
+#[repository_aliases]
+#stable = "debian_12"
+
+# Projects for the "report" and "watch" subcommands, with an optional pinned local version and the
+# repositories which are of interest.
+#[watchlist.curl]
+#version = "8.5.0"
+#repositories = ["debian_12", "fedora_40"]
+
+# Named profiles override the values above when selected with --profile NAME.
+#[profile.work]
+#repository_rules = ["allow fedora_*"]
+"#;
+
+pub fn run(app: &ArgMatches, mtch: &ArgMatches) -> Result<i32> {
+    match mtch.subcommand() {
+        Some(("show", _)) => show(&Configuration::load(app)?),
+        Some(("check", check_mtch)) => check(app, !check_mtch.get_flag("no-network")),
+        Some(("init", init_mtch)) => init(init_mtch.get_flag("force")),
+        _ => unreachable!("clap requires a subcommand"),
+    }
+}
+
+/// Print the effective configuration as TOML, with the source of each value as a comment
+fn show(config: &Configuration) -> Result<i32> {
+    fn value<T: Serialize>(key: &str, value: T) -> Result<String> {
+        let mut table = BTreeMap::new();
+        table.insert(key, value);
+        toml::to_string(&table).map_err(Error::from)
+    }
+
+    let mut out = std::io::stdout();
+    let mut print = |key: &str, source_key: &str, toml: String| -> Result<()> {
+        writeln!(out, "# {}: {}", key, config.source(source_key))?;
+        writeln!(out, "{}", toml.trim_end()).map_err(Error::from)
+    };
+
+    print(
+        "repology_url",
+        "repology_url",
+        value("repology_url", config.repology_url())?,
+    )?;
+    print(
+        "repository_rules",
+        "repository_rules",
+        value("repository_rules", config.repository_rules())?,
+    )?;
+    print(
+        "whitelist",
+        "whitelist",
+        value("whitelist", config.whitelist())?,
+    )?;
+    print(
+        "blacklist",
+        "blacklist",
+        value("blacklist", config.blacklist())?,
+    )?;
+
+    if let Some(mapping) = config.name_mapping() {
+        print(
+            "name_mapping",
+            "name_mapping",
+            value("name_mapping", mapping)?,
+        )?;
+    }
+
+    if let Some(ignore_file) = config.ignore_file() {
+        print(
+            "ignore_file",
+            "ignore_file",
+            value("ignore_file", ignore_file)?,
+        )?;
+    }
+
+    for (group, members) in config.repository_groups() {
+        let key = format!("repository_groups.{}", group);
+        let mut groups = BTreeMap::new();
+        groups.insert(group, members);
+        print(&key, &key, value("repository_groups", groups)?)?;
+    }
+
+    for (alias, repo) in config.repository_aliases() {
+        let key = format!("repository_aliases.{}", alias);
+        let mut aliases = BTreeMap::new();
+        aliases.insert(alias, repo);
+        print(&key, &key, value("repository_aliases", aliases)?)?;
+    }
+
+    for (command, format) in config.formats() {
+        let key = format!("formats.{}", command);
+        let mut formats = BTreeMap::new();
+        formats.insert(command, format);
+        print(&key, &key, value("formats", formats)?)?;
+    }
+
+    for (command, columns) in config.columns() {
+        let key = format!("columns.{}", command);
+        let mut commands = BTreeMap::new();
+        commands.insert(command, columns);
+        print(&key, &key, value("columns", commands)?)?;
+    }
+
+    for (status, color) in config.colors() {
+        let key = format!("colors.{}", status);
+        let mut colors = BTreeMap::new();
+        colors.insert(status, color);
+        print(&key, &key, value("colors", colors)?)?;
+    }
+
+    for (project, entry) in config.watchlist() {
+        let key = format!("watchlist.{}", project);
+        let mut projects = BTreeMap::new();
+        projects.insert(project, entry);
+        print(&key, &key, value("watchlist", projects)?)?;
+    }
+
+    Ok(crate::exitcode::SUCCESS)
+}
+
+/// Validate the configuration, print the findings and fail if there are errors
+fn check(app: &ArgMatches, network: bool) -> Result<i32> {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    for path in crate::config::config_files(app)? {
+        warnings.extend(unknown_keys(&path)?);
+    }
+
+    let config = Configuration::load(app)?;
+
+    if let Err(e) = RepoFilter::from_config(&config) {
+        errors.push(format!("{:#}", e));
+    }
+
+    config
+        .whitelist()
+        .iter()
+        .filter(|repo| config.blacklist().contains(repo))
+        .for_each(|repo| {
+            errors.push(format!(
+                "Repository '{}' is on both the whitelist and the blacklist",
+                repo
+            ))
+        });
+
+    if let Some(Err(e)) = config.name_mapping().map(|path| NameMapping::load(path)) {
+        errors.push(format!("{:#}", e));
+    }
+
+    match config.ignore_file().map(|path| IgnoreList::expired(path)) {
+        Some(Ok(expired)) => warnings.extend(
+            expired
+                .into_iter()
+                .map(|entry| format!("Expired acknowledgement in ignore file: {}", entry)),
+        ),
+        Some(Err(e)) => errors.push(format!("{:#}", e)),
+        None => {}
+    }
+
+    for (command, format) in config.formats() {
+        match crate::format::fields(command) {
+            Some(fields) => {
+                if let Err(e) = LineFormat::parse(format, fields) {
+                    errors.push(format!("formats.{}: {:#}", command, e));
+                }
+            }
+            None => warnings.push(format!(
+                "formats.{}: only the project and problems subcommands have a line format",
+                command
+            )),
+        }
+    }
+
+    for (command, columns) in config.columns() {
+        match crate::format::fields(command) {
+            Some(fields) => {
+                if let Err(e) = Columns::parse(columns, fields) {
+                    errors.push(format!("columns.{}: {:#}", command, e));
+                }
+            }
+            None => warnings.push(format!(
+                "columns.{}: only the project and problems subcommands have columns",
+                command
+            )),
+        }
+    }
+
+    if let Err(e) = Theme::new(config.colors()) {
+        errors.push(format!("{:#}", e));
+    }
+
+    config
+        .repository_aliases()
+        .keys()
+        .filter(|alias| config.repository_groups().contains_key(*alias))
+        .for_each(|name| {
+            errors.push(format!(
+                "'{}' is both a repository group and a repository alias",
+                name
+            ))
+        });
+
+    if network {
+        let backend = RestApi::new(config.repology_url().as_str().into());
+        errors.extend(check_repology(&backend, &config));
+    }
+
+    let mut out = std::io::stdout();
+    for warning in warnings.iter() {
+        writeln!(out, "warning: {}", warning)?;
+    }
+    for error in errors.iter() {
+        writeln!(out, "error: {}", error)?;
+    }
+
+    if errors.is_empty() {
+        writeln!(out, "Configuration OK")?;
+        Ok(crate::exitcode::SUCCESS)
+    } else {
+        Err(Error::from(UsageError::new(format!(
+            "Configuration check found {} error(s)",
+            errors.len()
+        ))))
+    }
+}
+
+/// Find keys in a configuration file which repolocli does not know, most likely typos
+fn unknown_keys(path: &Path) -> Result<Vec<String>> {
+    fn check_table(
+        path: &Path,
+        prefix: &str,
+        table: &toml::Table,
+        known: &[&str],
+        warnings: &mut Vec<String>,
+    ) {
+        table
+            .keys()
+            .filter(|key| !known.contains(&key.as_str()))
+            .for_each(|key| {
+                warnings.push(format!(
+                    "{}: unknown key '{}{}'",
+                    path.display(),
+                    prefix,
+                    key
+                ))
+            });
+
+        table
+            .get("watchlist")
+            .and_then(toml::Value::as_table)
+            .into_iter()
+            .flat_map(|watchlist| watchlist.iter())
+            .filter_map(|(project, entry)| entry.as_table().map(|entry| (project, entry)))
+            .for_each(|(project, entry)| {
+                let prefix = format!("{}watchlist.{}.", prefix, project);
+                check_table(path, &prefix, entry, KNOWN_WATCHLIST_KEYS, warnings)
+            });
+    }
+
+    let buffer = std::fs::read_to_string(path)
+        .with_context(|| format!("Reading configuration from {}", path.display()))?;
+    let table = buffer
+        .parse::<toml::Table>()
+        .map_err(Error::from)
+        .with_context(|| format!("Parsing configuration from {}", path.display()))?;
+
+    let mut warnings = Vec::new();
+    let mut known = KNOWN_KEYS.to_vec();
+    known.push("profile");
+    check_table(path, "", &table, &known, &mut warnings);
+
+    table
+        .get("profile")
+        .and_then(toml::Value::as_table)
+        .into_iter()
+        .flat_map(|profiles| profiles.iter())
+        .filter_map(|(name, profile)| profile.as_table().map(|profile| (name, profile)))
+        .for_each(|(name, profile)| {
+            let prefix = format!("profile.{}.", name);
+            check_table(path, &prefix, profile, KNOWN_KEYS, &mut warnings)
+        });
+
+    Ok(warnings)
+}
+
+/// Check that repology is reachable and knows all repositories named in the configuration
+///
+/// Only plain repository names are checked, not glob or regex patterns.
+fn check_repology(backend: &RestApi, config: &Configuration) -> Vec<String> {
+    // Any project name works, repology responds with an empty list for unknown projects
+    debug!("Checking whether {} is reachable", config.repology_url());
+    if let Err(e) = backend.project("repolocli") {
+        return vec![format!(
+            "repology_url {} is not reachable: {:#}",
+            config.repology_url(),
+            Error::from(e)
+        )];
+    }
+
+    let is_plain = |name: &str| !name.contains(['*', '?', '/']);
+    let rule_names = config
+        .repository_rules()
+        .iter()
+        .filter_map(|rule| rule.split_whitespace().nth(1));
+
+    let names = config
+        .whitelist()
+        .iter()
+        .chain(config.blacklist().iter())
+        .chain(config.repository_groups().keys())
+        .chain(config.repository_aliases().keys())
+        .chain(
+            config
+                .watchlist()
+                .values()
+                .flat_map(|entry| entry.repositories().iter()),
+        )
+        .map(String::as_str)
+        .chain(rule_names)
+        .filter(|name| is_plain(name))
+        .flat_map(|name| config.resolve_repository(name))
+        .collect::<BTreeSet<_>>();
+
+    names
+        .into_iter()
+        .filter_map(|name| {
+            debug!(
+                "Checking whether repology knows repository '{}'",
+                name.deref()
+            );
+            match backend.has_repository(name.as_str()) {
+                Ok(true) => None,
+                Ok(false) => Some(format!(
+                    "Repository '{}' is not known to repology",
+                    name.deref()
+                )),
+                Err(e) => Some(format!(
+                    "Could not check repository '{}': {:#}",
+                    name.deref(),
+                    Error::from(e)
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Write the starter configuration to the XDG config directory
+fn init(force: bool) -> Result<i32> {
+    let path = xdg::BaseDirectories::new()
+        .place_config_file("repolocli.toml")
+        .context("Finding location for configuration file")?;
+
+    if path.exists() && !force {
+        return Err(Error::from(UsageError::new(format!(
+            "{} already exists, pass --force to overwrite it",
+            path.display()
+        ))));
+    }
+
+    std::fs::write(&path, STARTER_CONFIG)
+        .with_context(|| format!("Writing configuration to {}", path.display()))?;
+    info!("Configuration written to {}", path.display());
+    Ok(crate::exitcode::SUCCESS)
+}
@@ -0,0 +1,339 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::ops::Deref;
+
+use anyhow::Result;
+use filters::filter::Filter;
+use librepology::v1::api::Api;
+use librepology::v1::types::Name;
+use librepology::v1::types::Package;
+use librepology::v1::types::Repo;
+use librepology::v1::types::Version;
+use librepology::v1::versioncmp;
+
+/// Which side of a diff has the newer version of a project
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+pub enum Newer {
+    #[serde(rename = "repo_a")]
+    RepoA,
+
+    #[serde(rename = "repo_b")]
+    RepoB,
+
+    #[serde(rename = "same")]
+    Same,
+}
+
+impl From<Ordering> for Newer {
+    fn from(ord: Ordering) -> Self {
+        match ord {
+            Ordering::Less => Newer::RepoB,
+            Ordering::Equal => Newer::Same,
+            Ordering::Greater => Newer::RepoA,
+        }
+    }
+}
+
+/// A project which is packaged in both repositories of a diff
+#[derive(Debug, Serialize)]
+pub struct DiffEntry {
+    project: Name,
+    version_a: Version,
+    version_b: Version,
+    newer: Newer,
+}
+
+impl DiffEntry {
+    pub fn project(&self) -> &Name {
+        &self.project
+    }
+
+    pub fn version_a(&self) -> &Version {
+        &self.version_a
+    }
+
+    pub fn version_b(&self) -> &Version {
+        &self.version_b
+    }
+
+    pub fn newer(&self) -> Newer {
+        self.newer
+    }
+}
+
+/// A project which is packaged in only one repository of a diff
+#[derive(Debug, Serialize)]
+pub struct SingleEntry {
+    project: Name,
+    version: Version,
+}
+
+impl SingleEntry {
+    pub fn project(&self) -> &Name {
+        &self.project
+    }
+
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+}
+
+/// The difference between two repositories
+#[derive(Debug, Serialize)]
+pub struct RepoDiff {
+    repo_a: Repo,
+    repo_b: Repo,
+
+    /// Projects packaged in both repositories
+    in_both: Vec<DiffEntry>,
+
+    /// Projects only packaged in `repo_a`
+    only_in_a: Vec<SingleEntry>,
+
+    /// Projects only packaged in `repo_b`
+    only_in_b: Vec<SingleEntry>,
+}
+
+impl RepoDiff {
+    pub fn repo_a(&self) -> &Repo {
+        &self.repo_a
+    }
+
+    pub fn repo_b(&self) -> &Repo {
+        &self.repo_b
+    }
+
+    pub fn in_both(&self) -> &Vec<DiffEntry> {
+        &self.in_both
+    }
+
+    pub fn only_in_a(&self) -> &Vec<SingleEntry> {
+        &self.only_in_a
+    }
+
+    pub fn only_in_b(&self) -> &Vec<SingleEntry> {
+        &self.only_in_b
+    }
+
+    /// Remove all projects which have the same version in both repositories
+    pub fn only_differing(mut self) -> Self {
+        self.in_both.retain(|entry| entry.newer != Newer::Same);
+        self
+    }
+}
+
+/// Find the newest version of all packages of a project in `repo`
+///
+/// A project might be packaged more than once in a repository (for example for different
+/// language versions), in which case the newest package is the relevant one.
+fn newest_in_repo<'a>(packages: &'a [Package], repo: &str) -> Option<&'a Version> {
+    packages
+        .iter()
+        .filter(|package| **package.repo() == repo)
+        .map(Package::version)
+        .max_by(|a, b| versioncmp::compare(a, b))
+}
+
+/// Compute the difference between the repositories `repo_a` and `repo_b`
+///
+/// Only packages which pass the `package_filter` are considered.
+pub fn diff_repos<A, F>(
+    backend: &A,
+    repo_a: &str,
+    repo_b: &str,
+    package_filter: &F,
+) -> Result<RepoDiff>
+where
+    A: Api,
+    F: Filter<Package>,
+{
+    debug!("Fetching projects in {}", repo_a);
+    let projects_a = backend.projects_in_repo(repo_a)?;
+    debug!("Fetching projects in {}", repo_b);
+    let projects_b = backend.projects_in_repo(repo_b)?;
+
+    // Projects from `projects_a` contain the packages of all repositories, so they are sufficient
+    // for finding the projects which are in both repositories
+    let mut all_projects: BTreeMap<Name, Vec<Package>> = projects_b;
+    all_projects.extend(projects_a);
+
+    let mut in_both = Vec::new();
+    let mut only_in_a = Vec::new();
+    let mut only_in_b = Vec::new();
+
+    for (project, mut packages) in all_projects {
+        packages.retain(|package| package_filter.filter(package));
+        match (
+            newest_in_repo(&packages, repo_a),
+            newest_in_repo(&packages, repo_b),
+        ) {
+            (Some(version_a), Some(version_b)) => {
+                trace!(
+                    "{}: {} vs {}",
+                    project.deref(),
+                    version_a.deref(),
+                    version_b.deref()
+                );
+                let newer = Newer::from(versioncmp::compare(version_a, version_b));
+                in_both.push(DiffEntry {
+                    project,
+                    version_a: version_a.clone(),
+                    version_b: version_b.clone(),
+                    newer,
+                });
+            }
+            (Some(version), None) => only_in_a.push(SingleEntry {
+                project,
+                version: version.clone(),
+            }),
+            (None, Some(version)) => only_in_b.push(SingleEntry {
+                project,
+                version: version.clone(),
+            }),
+            (None, None) => trace!("{} is in neither repository", project.deref()),
+        }
+    }
+
+    Ok(RepoDiff {
+        repo_a: Repo::new(repo_a.to_string()),
+        repo_b: Repo::new(repo_b.to_string()),
+        in_both,
+        only_in_a,
+        only_in_b,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use librepology::v1::api::Api;
+    use librepology::v1::error::Result;
+    use librepology::v1::types::Name;
+    use librepology::v1::types::Package;
+    use librepology::v1::types::Problem;
+
+    use super::diff_repos;
+    use super::Newer;
+    use super::RepoDiff;
+
+    /// A backend which knows the projects of a JSON fixture
+    struct Projects(BTreeMap<Name, Vec<Package>>);
+
+    impl Api for Projects {
+        fn project<N: AsRef<str>>(&self, _name: N) -> Result<Vec<Package>> {
+            unreachable!("not used for diffs")
+        }
+
+        fn projects_in_repo<R: AsRef<str>>(&self, repo: R) -> Result<BTreeMap<Name, Vec<Package>>> {
+            Ok(self
+                .0
+                .iter()
+                .filter(|(_, packages)| packages.iter().any(|p| **p.repo() == repo.as_ref()))
+                .map(|(name, packages)| (name.clone(), packages.clone()))
+                .collect())
+        }
+
+        fn problems_for_repo<R: AsRef<str>>(&self, _repo: R) -> Result<Vec<Problem>> {
+            unreachable!("not used for diffs")
+        }
+
+        fn problems_for_maintainer<M: AsRef<str>, R: AsRef<str>>(
+            &self,
+            _maintainer: M,
+            _repo: R,
+        ) -> Result<Vec<Problem>> {
+            unreachable!("not used for diffs")
+        }
+    }
+
+    fn diff() -> RepoDiff {
+        let projects = serde_json::from_str(
+            r#"{
+                "curl": [
+                    {"repo": "debian_12", "version": "7.88.1"},
+                    {"repo": "arch", "version": "8.10.1"}
+                ],
+                "python": [
+                    {"repo": "debian_12", "version": "2.7.18"},
+                    {"repo": "debian_12", "version": "3.11.2"},
+                    {"repo": "arch", "version": "3.11.2"}
+                ],
+                "openssl": [
+                    {"repo": "debian_12", "version": "3.0.15"},
+                    {"repo": "arch", "version": "3.0.9"}
+                ],
+                "dpkg": [{"repo": "debian_12", "version": "1.21.22"}],
+                "pacman": [{"repo": "arch", "version": "7.0.0"}],
+                "rpm": [{"repo": "fedora_40", "version": "4.19.1"}]
+            }"#,
+        )
+        .unwrap();
+
+        diff_repos(&Projects(projects), "debian_12", "arch", &|_: &Package| {
+            true
+        })
+        .unwrap()
+    }
+
+    fn newer(diff: &RepoDiff, project: &str) -> Option<Newer> {
+        diff.in_both()
+            .iter()
+            .find(|entry| **entry.project() == project)
+            .map(|entry| entry.newer())
+    }
+
+    #[test]
+    fn test_in_both() {
+        let diff = diff();
+        assert_eq!(newer(&diff, "curl"), Some(Newer::RepoB));
+        assert_eq!(newer(&diff, "openssl"), Some(Newer::RepoA));
+
+        // The newest package of a repository is compared
+        assert_eq!(newer(&diff, "python"), Some(Newer::Same));
+        assert_eq!(diff.in_both().len(), 3);
+    }
+
+    #[test]
+    fn test_only_in_one() {
+        let diff = diff();
+        let only_in_a = diff
+            .only_in_a()
+            .iter()
+            .map(|entry| (entry.project().as_str(), entry.version().as_str()))
+            .collect::<Vec<_>>();
+        let only_in_b = diff
+            .only_in_b()
+            .iter()
+            .map(|entry| (entry.project().as_str(), entry.version().as_str()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(only_in_a, vec![("dpkg", "1.21.22")]);
+        assert_eq!(only_in_b, vec![("pacman", "7.0.0")]);
+    }
+
+    #[test]
+    fn test_only_differing() {
+        let diff = diff().only_differing();
+        assert_eq!(newer(&diff, "python"), None);
+        assert_eq!(diff.in_both().len(), 2);
+        assert_eq!(diff.only_in_a().len(), 1);
+        assert_eq!(diff.only_in_b().len(), 1);
+    }
+
+    #[test]
+    fn test_package_filter() {
+        let projects = serde_json::from_str(
+            r#"{"curl": [
+                {"repo": "debian_12", "version": "7.88.1"},
+                {"repo": "arch", "version": "8.10.1"}
+            ]}"#,
+        )
+        .unwrap();
+        let filter = |package: &Package| **package.repo() != "arch";
+        let diff = diff_repos(&Projects(projects), "debian_12", "arch", &filter).unwrap();
+
+        assert!(diff.in_both().is_empty());
+        assert_eq!(diff.only_in_a().len(), 1);
+    }
+}
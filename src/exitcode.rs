@@ -0,0 +1,174 @@
+//! Process exit codes
+//!
+//! repolocli exits with one of the following codes:
+//!
+//! | Code | Meaning                                                    |
+//! | ---- | ---------------------------------------------------------- |
+//! | 0    | Success                                                    |
+//! | 1    | Unspecified error                                          |
+//! | 2    | Usage error (invalid commandline, unknown profile)         |
+//! | 3    | Network error (repology could not be reached)              |
+//! | 4    | API error (repology responded with an error)               |
+//! | 5    | Parse error (data or configuration could not be parsed)    |
+//! | 10   | The query succeeded, but results matched `--fail-on`, or   |
+//! |      | 'check' found violations of the policy                     |
+//!
+
+use std::fmt;
+
+use anyhow::Error;
+use clap::ArgMatches;
+use librepology::v1::error::RepologyError;
+use librepology::v1::types::Package;
+use librepology::v1::types::Problem;
+use librepology::v1::types::Status;
+
+use crate::relative::Relation;
+use crate::report::Report;
+use crate::report::ReportEntry;
+
+pub const SUCCESS: i32 = 0;
+pub const FAILURE: i32 = 1;
+pub const USAGE: i32 = 2;
+pub const NETWORK: i32 = 3;
+pub const API: i32 = 4;
+pub const PARSE: i32 = 5;
+pub const FAIL_ON: i32 = 10;
+
+/// Error type for errors in how repolocli was invoked
+#[derive(Debug)]
+pub struct UsageError(String);
+
+impl UsageError {
+    pub fn new<S: Into<String>>(msg: S) -> Self {
+        UsageError(msg.into())
+    }
+}
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for UsageError {}
+
+/// Find the exit code for an error
+///
+/// The first error in the chain of causes which can be classified decides.
+pub fn for_error(e: &Error) -> i32 {
+    e.chain()
+        .find_map(|cause| {
+            if cause.is::<UsageError>() {
+                Some(USAGE)
+            } else if let Some(err) = cause.downcast_ref::<RepologyError>() {
+                match err {
+                    RepologyError::CurlError(_) => Some(NETWORK),
+                    RepologyError::HttpStatus(_) => Some(API),
+                    RepologyError::SerdeJsonError(_) | RepologyError::Utf8Error(_) => Some(PARSE),
+                    _ => None,
+                }
+            } else if cause.is::<serde_json::Error>() || cause.is::<toml::de::Error>() {
+                Some(PARSE)
+            } else {
+                None
+            }
+        })
+        .unwrap_or(FAILURE)
+}
+
+/// The conditions passed with `--fail-on`, under which a successful query results in a non-zero
+/// exit code
+#[derive(Debug, Default)]
+pub struct FailOn {
+    outdated: bool,
+    problems: bool,
+    vulnerable: bool,
+}
+
+/// The `--fail-on` conditions which can be checked on the results of each subcommand
+const FAIL_ON_CONDITIONS: &[(&str, &[&str])] = &[
+    ("project", &["outdated", "vulnerable"]),
+    ("problems", &["problems"]),
+    ("report", &["outdated", "vulnerable"]),
+];
+
+impl FailOn {
+    /// Read the conditions from `--fail-on`
+    ///
+    /// Fails with a usage error for conditions which cannot be checked on the results of the
+    /// subcommand, instead of silently ignoring them.
+    pub fn from_matches(app: &ArgMatches) -> Result<Self, Error> {
+        let command = app.subcommand_name().unwrap_or_default();
+        let supported = FAIL_ON_CONDITIONS
+            .iter()
+            .find(|(name, _)| *name == command)
+            .map(|(_, conditions)| *conditions)
+            .unwrap_or(&[]);
+
+        let mut fail_on = FailOn::default();
+        for condition in app.get_many::<String>("fail-on").into_iter().flatten() {
+            if !supported.contains(&condition.as_str()) {
+                return Err(Error::from(UsageError::new(format!(
+                    "--fail-on {} cannot be used with the '{}' subcommand",
+                    condition, command
+                ))));
+            }
+
+            match condition.as_ref() {
+                "outdated" => fail_on.outdated = true,
+                "problems" => fail_on.problems = true,
+                "vulnerable" => fail_on.vulnerable = true,
+                other => unreachable!(
+                    "Unknown --fail-on condition '{}', clap should prevent this",
+                    other
+                ),
+            }
+        }
+        Ok(fail_on)
+    }
+
+    /// Whether `package` matches one of the conditions
+    pub fn package(&self, package: &Package) -> bool {
+        (self.outdated && package.status() == Some(&Status::Outdated))
+            || (self.vulnerable && package.is_vulnerable())
+    }
+
+    /// Whether any of `packages` matches one of the conditions
+    pub fn packages(&self, packages: &[Package]) -> bool {
+        packages.iter().any(|package| self.package(package))
+    }
+
+    /// Whether `problems` match one of the conditions
+    pub fn problems(&self, problems: &[Problem]) -> bool {
+        self.problems && !problems.is_empty()
+    }
+
+    /// Whether the `report` contains a project matching one of the conditions
+    ///
+    /// A pinned version which is older than the newest version counts as outdated.
+    pub fn report(&self, report: &Report) -> bool {
+        let outdated = |entry: &ReportEntry| {
+            let pinned_outdated = entry
+                .pinned()
+                .map(|pinned| pinned.relation() == Some(Relation::Older))
+                .unwrap_or(false);
+
+            pinned_outdated
+                || entry
+                    .repositories()
+                    .iter()
+                    .any(|state| state.status() == Some(&Status::Outdated))
+        };
+        let vulnerable = |entry: &ReportEntry| {
+            entry
+                .repositories()
+                .iter()
+                .any(|state| state.is_vulnerable())
+        };
+
+        report.entries().iter().any(|entry| {
+            (self.outdated && outdated(entry)) || (self.vulnerable && vulnerable(entry))
+        })
+    }
+}
@@ -0,0 +1,761 @@
+//! Filter expressions
+//!
+//! Expressions are passed with `--filter` and select packages and problems by their fields, for
+//! example:
+//!
+//! ```text
+//! status == outdated && repo ~ "debian_*" && maintainer contains "@example.org"
+//! ```
+//!
+//! An expression is a comparison of a field with a value, and comparisons can be combined with
+//! `&&`, `||`, `!` and parentheses. `&&` binds stronger than `||`.
+//!
+//! The operators are:
+//!
+//! * `==` and `!=` for (in)equality
+//! * `~` and `!~` for matching against a glob pattern, where `*` matches anything
+//! * `contains` for substring matching
+//! * `<`, `<=`, `>` and `>=` for comparing versions
+//!
+//! Values are either bare words or double-quoted strings. If a field has multiple values (for
+//! example `maintainer`), the comparison matches if it matches for one of the values (`!=` and
+//! `!~` match if the comparison matches for none of the values). If a field has no value for an
+//! element, only `!=` and `!~` match.
+//!
+//! Fields of packages: `project`, `repo`, `name`, `srcname`, `binname`, `visiblename`, `version`,
+//! `status`, `summary`, `license`, `maintainer`, `www`, `download`, `vulnerable`.
+//!
+//! Fields of problems: `project`, `repo`, `maintainer`, `type`, `srcname`, `version`.
+//!
+//! Using a field which the filtered elements do not have is an error, for example `status` for
+//! problems or `type` for packages.
+//!
+//! `repo == NAME` and `repo != NAME` accept repository groups and aliases from the configuration.
+
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::ops::Deref;
+use std::str::Chars;
+
+use anyhow::Error;
+use anyhow::Result;
+use filters::filter::Filter;
+use filters::ops::bool::Bool;
+use librepology::v1::types::Package;
+use librepology::v1::types::Problem;
+use librepology::v1::versioncmp;
+
+use crate::config::Configuration;
+use crate::exitcode::UsageError;
+use crate::pattern::Pattern;
+
+/// A field of a package or problem which can be used in an expression
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Field {
+    Project,
+    Repo,
+    Name,
+    SrcName,
+    BinName,
+    VisibleName,
+    Version,
+    Status,
+    Summary,
+    License,
+    Maintainer,
+    Www,
+    Download,
+    Vulnerable,
+    ProblemType,
+}
+
+impl Field {
+    fn parse(s: &str) -> Option<Field> {
+        match s {
+            "project" => Some(Field::Project),
+            "repo" | "repository" => Some(Field::Repo),
+            "name" => Some(Field::Name),
+            "srcname" => Some(Field::SrcName),
+            "binname" => Some(Field::BinName),
+            "visiblename" => Some(Field::VisibleName),
+            "version" => Some(Field::Version),
+            "status" => Some(Field::Status),
+            "summary" => Some(Field::Summary),
+            "license" | "licenses" => Some(Field::License),
+            "maintainer" | "maintainers" => Some(Field::Maintainer),
+            "www" => Some(Field::Www),
+            "download" | "downloads" => Some(Field::Download),
+            "vulnerable" => Some(Field::Vulnerable),
+            "type" => Some(Field::ProblemType),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Field::Project => "project",
+            Field::Repo => "repo",
+            Field::Name => "name",
+            Field::SrcName => "srcname",
+            Field::BinName => "binname",
+            Field::VisibleName => "visiblename",
+            Field::Version => "version",
+            Field::Status => "status",
+            Field::Summary => "summary",
+            Field::License => "license",
+            Field::Maintainer => "maintainer",
+            Field::Www => "www",
+            Field::Download => "download",
+            Field::Vulnerable => "vulnerable",
+            Field::ProblemType => "type",
+        }
+    }
+}
+
+/// Access to the values of the fields of an element, for evaluating expressions on it
+pub trait Fields {
+    /// The name of the elements, for error messages
+    const KIND: &'static str;
+
+    /// The fields the elements have
+    const FIELDS: &'static [Field];
+
+    fn field_values(&self, field: Field) -> Vec<String>;
+}
+
+impl Fields for Package {
+    const KIND: &'static str = "packages";
+    const FIELDS: &'static [Field] = &[
+        Field::Project,
+        Field::Repo,
+        Field::Name,
+        Field::SrcName,
+        Field::BinName,
+        Field::VisibleName,
+        Field::Version,
+        Field::Status,
+        Field::Summary,
+        Field::License,
+        Field::Maintainer,
+        Field::Www,
+        Field::Download,
+        Field::Vulnerable,
+    ];
+
+    fn field_values(&self, field: Field) -> Vec<String> {
+        fn list<T: Deref<Target = String>>(o: Option<&Vec<T>>) -> Vec<String> {
+            o.into_iter()
+                .flat_map(|v| v.iter().map(|e| e.deref().clone()))
+                .collect()
+        }
+
+        fn urls<T, U>(o: Option<&Vec<T>>) -> Vec<String>
+        where
+            T: Deref<Target = U>,
+            U: ToString,
+        {
+            o.into_iter()
+                .flat_map(|v| v.iter().map(|e| e.deref().to_string()))
+                .collect()
+        }
+
+        match field {
+            Field::Project => self
+                .effname()
+                .map(|n| n.deref().clone())
+                .into_iter()
+                .collect(),
+            Field::Repo => vec![self.repo().deref().clone()],
+            Field::Name => self.name().map(|n| n.deref().clone()).into_iter().collect(),
+            Field::SrcName => self
+                .srcname()
+                .map(|n| n.deref().clone())
+                .into_iter()
+                .collect(),
+            Field::BinName => self
+                .binname()
+                .map(|n| n.deref().clone())
+                .into_iter()
+                .collect(),
+            Field::VisibleName => self
+                .visiblename()
+                .map(|n| n.deref().clone())
+                .into_iter()
+                .collect(),
+            Field::Version => vec![self.version().deref().clone()],
+            Field::Status => self.status().map(|s| s.to_string()).into_iter().collect(),
+            Field::Summary => self
+                .summary()
+                .map(|s| s.deref().clone())
+                .into_iter()
+                .collect(),
+            Field::License => list(self.licenses()),
+            Field::Maintainer => list(self.maintainers()),
+            Field::Www => urls(self.www()),
+            Field::Download => urls(self.downloads()),
+            Field::Vulnerable => vec![self.is_vulnerable().to_string()],
+            Field::ProblemType => vec![],
+        }
+    }
+}
+
+impl Fields for Problem {
+    const KIND: &'static str = "problems";
+    const FIELDS: &'static [Field] = &[
+        Field::Project,
+        Field::Repo,
+        Field::Maintainer,
+        Field::ProblemType,
+        Field::SrcName,
+        Field::Version,
+    ];
+
+    fn field_values(&self, field: Field) -> Vec<String> {
+        match field {
+            Field::Project => vec![self.project_name().deref().clone()],
+            Field::Repo => self.repo().map(|r| r.deref().clone()).into_iter().collect(),
+            Field::Maintainer => vec![self.maintainer().deref().clone()],
+            Field::ProblemType => vec![self.problem_type().clone()],
+            Field::SrcName => self.srcname().cloned().into_iter().collect(),
+            Field::Version => self
+                .version()
+                .map(|v| v.deref().clone())
+                .into_iter()
+                .collect(),
+            _ => vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Operator {
+    Eq,
+    NotEq,
+    Matches,
+    NotMatches,
+    Contains,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+}
+
+impl std::fmt::Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let s = match self {
+            Operator::Eq => "==",
+            Operator::NotEq => "!=",
+            Operator::Matches => "~",
+            Operator::NotMatches => "!~",
+            Operator::Contains => "contains",
+            Operator::Less => "<",
+            Operator::LessEq => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterEq => ">=",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// A comparison of a field with a value, the leaf of an expression
+#[derive(Debug, Clone)]
+struct Comparison {
+    field: Field,
+    operator: Operator,
+    value: String,
+}
+
+impl<T: Fields> Filter<T> for Comparison {
+    fn filter(&self, element: &T) -> bool {
+        let values = element.field_values(self.field);
+        let version_cmp = |v: &String, accept: fn(Ordering) -> bool| {
+            accept(versioncmp::compare_str(v, &self.value))
+        };
+
+        match self.operator {
+            Operator::Eq => values.contains(&self.value),
+            Operator::NotEq => !values.contains(&self.value),
+            Operator::Matches => {
+                let pattern = Pattern::new(self.value.as_str());
+                values.iter().any(|v| pattern.matches(v))
+            }
+            Operator::NotMatches => {
+                let pattern = Pattern::new(self.value.as_str());
+                !values.iter().any(|v| pattern.matches(v))
+            }
+            Operator::Contains => values.iter().any(|v| v.contains(&self.value)),
+            Operator::Less => values.iter().any(|v| version_cmp(v, Ordering::is_lt)),
+            Operator::LessEq => values.iter().any(|v| version_cmp(v, Ordering::is_le)),
+            Operator::Greater => values.iter().any(|v| version_cmp(v, Ordering::is_gt)),
+            Operator::GreaterEq => values.iter().any(|v| version_cmp(v, Ordering::is_ge)),
+        }
+    }
+}
+
+/// The parsed form of an expression
+#[derive(Debug, Clone)]
+enum Ast {
+    And(Box<Ast>, Box<Ast>),
+    Or(Box<Ast>, Box<Ast>),
+    Not(Box<Ast>),
+    Comparison(Comparison),
+}
+
+/// A filter built from an expression
+///
+/// This wraps the combination of filters built from the expression, so that it can be named.
+pub struct ExpressionFilter<T>(Box<dyn Filter<T>>);
+
+impl<T> Filter<T> for ExpressionFilter<T> {
+    fn filter(&self, element: &T) -> bool {
+        self.0.filter(element)
+    }
+}
+
+impl Ast {
+    /// Replace repository groups and aliases in `repo ==` and `repo !=` comparisons by the
+    /// repositories they stand for
+    fn resolve_repositories(self, config: &Configuration) -> Ast {
+        match self {
+            Ast::And(a, b) => Ast::And(
+                Box::new(a.resolve_repositories(config)),
+                Box::new(b.resolve_repositories(config)),
+            ),
+            Ast::Or(a, b) => Ast::Or(
+                Box::new(a.resolve_repositories(config)),
+                Box::new(b.resolve_repositories(config)),
+            ),
+            Ast::Not(a) => Ast::Not(Box::new(a.resolve_repositories(config))),
+            Ast::Comparison(c)
+                if c.field == Field::Repo
+                    && (c.operator == Operator::Eq || c.operator == Operator::NotEq) =>
+            {
+                let combine = if c.operator == Operator::Eq {
+                    Ast::Or
+                } else {
+                    Ast::And
+                };
+
+                config
+                    .resolve_repository(&c.value)
+                    .into_iter()
+                    .map(|repo| {
+                        Ast::Comparison(Comparison {
+                            field: c.field,
+                            operator: c.operator,
+                            value: repo.to_string(),
+                        })
+                    })
+                    .reduce(|a, b| combine(Box::new(a), Box::new(b)))
+                    .unwrap_or(Ast::Comparison(c))
+            }
+            Ast::Comparison(c) => Ast::Comparison(c),
+        }
+    }
+
+    /// The fields used in the expression
+    fn fields(&self) -> Vec<Field> {
+        match self {
+            Ast::And(a, b) | Ast::Or(a, b) => {
+                let mut fields = a.fields();
+                fields.extend(b.fields());
+                fields
+            }
+            Ast::Not(a) => a.fields(),
+            Ast::Comparison(c) => vec![c.field],
+        }
+    }
+
+    fn build<T: Fields + 'static>(&self) -> ExpressionFilter<T> {
+        match self {
+            Ast::And(a, b) => ExpressionFilter(Box::new(a.build::<T>().and(b.build::<T>()))),
+            Ast::Or(a, b) => ExpressionFilter(Box::new(a.build::<T>().or(b.build::<T>()))),
+            Ast::Not(a) => ExpressionFilter(Box::new(a.build::<T>().not())),
+            Ast::Comparison(c) => ExpressionFilter(Box::new(c.clone())),
+        }
+    }
+}
+
+/// A parsed filter expression
+#[derive(Debug)]
+pub struct Expression(Ast);
+
+impl Expression {
+    pub fn parse(s: &str) -> Result<Expression> {
+        let tokens = tokenize(s)?;
+        trace!("Expression tokens: {:?}", tokens);
+
+        let mut parser = Parser { tokens, pos: 0 };
+        let ast = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(Expression(ast)),
+            Some(token) => Err(parse_error(format!("Unexpected '{}'", token))),
+        }
+    }
+
+    /// Replace repository groups and aliases from the configuration in the expression
+    pub fn resolve_repositories(self, config: &Configuration) -> Expression {
+        Expression(self.0.resolve_repositories(config))
+    }
+}
+
+/// Build the filter for an optional expression
+///
+/// Without an expression, everything passes the filter. Fails if the expression uses a field the
+/// elements do not have, as the comparison would silently drop every element.
+pub fn filter_for<T: Fields + 'static>(
+    expression: Option<&Expression>,
+) -> Result<ExpressionFilter<T>> {
+    match expression {
+        Some(expression) => {
+            let unsupported = expression
+                .0
+                .fields()
+                .into_iter()
+                .find(|field| !T::FIELDS.contains(field));
+
+            match unsupported {
+                Some(field) => Err(parse_error(format!(
+                    "The field '{}' cannot be used for {}",
+                    field.name(),
+                    T::KIND
+                ))),
+                None => Ok(expression.0.build()),
+            }
+        }
+        None => Ok(ExpressionFilter(Box::new(Bool::new(true)))),
+    }
+}
+
+fn parse_error(msg: String) -> Error {
+    Error::from(UsageError::new(format!(
+        "Invalid filter expression: {}",
+        msg
+    )))
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum Token {
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+    Operator(Operator),
+    Word(String),
+    Str(String),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Token::And => write!(f, "&&"),
+            Token::Or => write!(f, "||"),
+            Token::Not => write!(f, "!"),
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")"),
+            Token::Operator(op) => write!(f, "{}", op),
+            Token::Word(w) => write!(f, "{}", w),
+            Token::Str(s) => write!(f, "\"{}\"", s),
+        }
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>> {
+    fn next_is(chars: &mut Peekable<Chars>, c: char) -> bool {
+        if chars.peek() == Some(&c) {
+            chars.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '~' => Token::Operator(Operator::Matches),
+            '&' if next_is(&mut chars, '&') => Token::And,
+            '|' if next_is(&mut chars, '|') => Token::Or,
+            '=' if next_is(&mut chars, '=') => Token::Operator(Operator::Eq),
+            '!' if next_is(&mut chars, '=') => Token::Operator(Operator::NotEq),
+            '!' if next_is(&mut chars, '~') => Token::Operator(Operator::NotMatches),
+            '!' => Token::Not,
+            '<' if next_is(&mut chars, '=') => Token::Operator(Operator::LessEq),
+            '<' => Token::Operator(Operator::Less),
+            '>' if next_is(&mut chars, '=') => Token::Operator(Operator::GreaterEq),
+            '>' => Token::Operator(Operator::Greater),
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c) => string.push(c),
+                            None => return Err(parse_error(String::from("Unterminated string"))),
+                        },
+                        Some(c) => string.push(c),
+                        None => return Err(parse_error(String::from("Unterminated string"))),
+                    }
+                }
+                Token::Str(string)
+            }
+            c if !"&|=!<>()~\"".contains(c) => {
+                let mut word = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "&|=!<>()~\"".contains(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+
+                if word == "contains" {
+                    Token::Operator(Operator::Contains)
+                } else {
+                    Token::Word(word)
+                }
+            }
+            other => return Err(parse_error(format!("Unexpected character '{}'", other))),
+        };
+
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser for expressions
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn next_is(&mut self, token: &Token) -> bool {
+        if self.tokens.get(self.pos) == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<Ast> {
+        let mut ast = self.and()?;
+        while self.next_is(&Token::Or) {
+            ast = Ast::Or(Box::new(ast), Box::new(self.and()?));
+        }
+        Ok(ast)
+    }
+
+    fn and(&mut self) -> Result<Ast> {
+        let mut ast = self.unary()?;
+        while self.next_is(&Token::And) {
+            ast = Ast::And(Box::new(ast), Box::new(self.unary()?));
+        }
+        Ok(ast)
+    }
+
+    fn unary(&mut self) -> Result<Ast> {
+        if self.next_is(&Token::Not) {
+            Ok(Ast::Not(Box::new(self.unary()?)))
+        } else if self.next_is(&Token::Open) {
+            let ast = self.or()?;
+            if self.next_is(&Token::Close) {
+                Ok(ast)
+            } else {
+                Err(parse_error(String::from("Missing ')'")))
+            }
+        } else {
+            self.comparison()
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Ast> {
+        let field = match self.next() {
+            Some(Token::Word(w)) => {
+                Field::parse(&w).ok_or_else(|| parse_error(format!("Unknown field '{}'", w)))?
+            }
+            Some(other) => return Err(parse_error(format!("Expected field, found '{}'", other))),
+            None => return Err(parse_error(String::from("Expected field, found end"))),
+        };
+
+        let operator = match self.next() {
+            Some(Token::Operator(op)) => op,
+            Some(other) => {
+                return Err(parse_error(format!("Expected operator, found '{}'", other)))
+            }
+            None => return Err(parse_error(String::from("Expected operator, found end"))),
+        };
+
+        let value = match self.next() {
+            Some(Token::Word(w)) | Some(Token::Str(w)) => w,
+            Some(other) => return Err(parse_error(format!("Expected value, found '{}'", other))),
+            None => return Err(parse_error(String::from("Expected value, found end"))),
+        };
+
+        Ok(Ast::Comparison(Comparison {
+            field,
+            operator,
+            value,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use filters::filter::Filter;
+    use librepology::v1::types::Package;
+    use librepology::v1::types::Problem;
+
+    use super::filter_for;
+    use super::tokenize;
+    use super::Expression;
+    use super::Operator;
+    use super::Token;
+
+    fn package(json: &str) -> Package {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn matches(expression: &str, package: &Package) -> bool {
+        let expression = Expression::parse(expression).unwrap();
+        filter_for::<Package>(Some(&expression))
+            .unwrap()
+            .filter(package)
+    }
+
+    fn curl() -> Package {
+        package(
+            r#"{"repo": "debian_12", "version": "7.88.1", "effname": "curl", "status": "outdated",
+                "maintainers": ["a@debian.org", "b@example.org"], "vulnerable": true}"#,
+        )
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let tokens = tokenize(r#"!(repo!~"a b") && version>=1.0 || name contains x"#).unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Not,
+                Token::Open,
+                Token::Word(String::from("repo")),
+                Token::Operator(Operator::NotMatches),
+                Token::Str(String::from("a b")),
+                Token::Close,
+                Token::And,
+                Token::Word(String::from("version")),
+                Token::Operator(Operator::GreaterEq),
+                Token::Word(String::from("1.0")),
+                Token::Or,
+                Token::Word(String::from("name")),
+                Token::Operator(Operator::Contains),
+                Token::Word(String::from("x")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_string_escapes() {
+        let tokens = tokenize(r#""a \"quoted\" \\ value""#).unwrap();
+        assert_eq!(
+            tokens,
+            vec![Token::Str(String::from(r#"a "quoted" \ value"#))]
+        );
+        assert!(tokenize(r#""unterminated"#).is_err());
+        assert!(tokenize("a & b").is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Expression::parse("").is_err());
+        assert!(Expression::parse("repo").is_err());
+        assert!(Expression::parse("repo ==").is_err());
+        assert!(Expression::parse("unknown == x").is_err());
+        assert!(Expression::parse("(repo == x").is_err());
+        assert!(Expression::parse("repo == x)").is_err());
+        assert!(Expression::parse("repo == x &&").is_err());
+    }
+
+    #[test]
+    fn test_operators() {
+        let curl = curl();
+        assert!(matches("repo == debian_12", &curl));
+        assert!(matches("repo != arch", &curl));
+        assert!(matches(r#"repo ~ "debian_*""#, &curl));
+        assert!(matches(r#"repo !~ "fedora_*""#, &curl));
+        assert!(matches("maintainer contains example.org", &curl));
+        assert!(matches("version < 7.100", &curl));
+        assert!(matches("version <= 7.88.1", &curl));
+        assert!(matches("version > 7.9", &curl));
+        assert!(!matches("version >= 8", &curl));
+        assert!(matches("vulnerable == true", &curl));
+    }
+
+    #[test]
+    fn test_missing_values() {
+        let curl = curl();
+        assert!(!matches("summary == x", &curl));
+        assert!(!matches("summary ~ *", &curl));
+        assert!(matches("summary != x", &curl));
+        assert!(matches("summary !~ *", &curl));
+    }
+
+    #[test]
+    fn test_precedence() {
+        let curl = curl();
+        // `&&` binds stronger than `||`
+        assert!(matches(
+            "repo == arch && status == newest || status == outdated",
+            &curl
+        ));
+        assert!(!matches(
+            "repo == arch && (status == newest || status == outdated)",
+            &curl
+        ));
+        assert!(matches("!repo == arch", &curl));
+        assert!(!matches("!(repo == debian_12 || repo == arch)", &curl));
+    }
+
+    #[test]
+    fn test_unsupported_fields() {
+        let expression = Expression::parse("status == outdated").unwrap();
+        assert!(filter_for::<Package>(Some(&expression)).is_ok());
+        assert!(filter_for::<Problem>(Some(&expression)).is_err());
+
+        let expression = Expression::parse("type == x || project == curl").unwrap();
+        assert!(filter_for::<Problem>(Some(&expression)).is_ok());
+        assert!(filter_for::<Package>(Some(&expression)).is_err());
+    }
+
+    #[test]
+    fn test_problem_repo() {
+        let problem = |repo: &str| -> Problem {
+            serde_json::from_str(&format!(
+                r#"{{"repo": "{}", "project_name": "curl", "maintainer": "a@debian.org",
+                    "type": "bad license"}}"#,
+                repo
+            ))
+            .unwrap()
+        };
+        let expression =
+            Expression::parse(r#"repo ~ "debian_*" && type == "bad license""#).unwrap();
+        let filter = filter_for::<Problem>(Some(&expression)).unwrap();
+
+        assert!(filter.filter(&problem("debian_12")));
+        assert!(!filter.filter(&problem("fedora_40")));
+    }
+}
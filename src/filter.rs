@@ -1,49 +1,264 @@
+//! Repository rules
+//!
+//! Which repositories are shown is decided by an ordered list of `allow` and `deny` rules. The
+//! pattern of a rule is either a glob (`debian_*`, see `Pattern`) or, if it is enclosed in
+//! slashes, a regular expression (`/^fedora_\d+$/`). Regular expressions are not anchored
+//! implicitly. A pattern which is the name of a repository group or alias from the configuration
+//! matches the repositories of the group or alias.
+//!
+//! Precedence:
+//!
+//! 1. The rules are checked in order and the first rule matching the repository decides.
+//! 2. If no rule matches, the repository is denied if there is at least one `allow` rule, and
+//!    allowed otherwise.
+//!
+//! The rules are taken from the commandline (`--allow-repo` and `--deny-repo`, in the order in
+//! which they are passed) if any are given there. Otherwise they are taken from the
+//! configuration: first `repository_rules` in order, then a `deny` rule for each entry of
+//! `blacklist`, then an `allow` rule for each entry of `whitelist`.
+
+use std::ops::Deref;
+
+use anyhow::Context;
+use anyhow::Error;
+use anyhow::Result;
+use clap::ArgMatches;
 use filters::filter::Filter;
-use filters::ops::and::And;
-use filters::ops::bool::Bool;
-use filters::ops::not::Not;
+use librepology::v1::types::Repo;
+use regex::Regex;
 
 use crate::config::Configuration;
+use crate::exitcode::UsageError;
+use crate::pattern::Pattern;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Action {
+    Allow,
+    Deny,
+}
+
+/// The pattern of a rule
+#[derive(Debug, Clone)]
+pub enum RepoPattern {
+    Glob(Pattern),
+    Regex(Regex),
 
-struct BlackListFilter {
-    repo_name: String,
+    /// The repositories of a group or alias
+    Repos(Vec<Repo>),
 }
 
-impl BlackListFilter {
-    pub fn new(repo_name: String) -> Self {
-        BlackListFilter { repo_name }
+impl RepoPattern {
+    /// Parse a pattern, which is a regular expression if enclosed in slashes, a group or alias if
+    /// the configuration has one with that name, a glob otherwise
+    pub fn parse(s: &str, config: &Configuration) -> Result<Self> {
+        if config.repository_groups().contains_key(s) || config.repository_aliases().contains_key(s)
+        {
+            Ok(RepoPattern::Repos(config.resolve_repository(s)))
+        } else if s.len() >= 2 && s.starts_with('/') && s.ends_with('/') {
+            Regex::new(&s[1..s.len() - 1])
+                .map(RepoPattern::Regex)
+                .map_err(Error::from)
+                .with_context(|| {
+                    format!("Invalid regular expression in repository pattern '{}'", s)
+                })
+        } else {
+            Ok(RepoPattern::Glob(Pattern::new(s)))
+        }
     }
+
+    pub fn matches(&self, repo: &str) -> bool {
+        match self {
+            RepoPattern::Glob(pattern) => pattern.matches(repo),
+            RepoPattern::Regex(regex) => regex.is_match(repo),
+            RepoPattern::Repos(repos) => repos.iter().any(|r| r.deref() == repo),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Rule {
+    action: Action,
+    pattern: RepoPattern,
 }
 
-impl Filter<String> for BlackListFilter {
-    fn filter(&self, element: &String) -> bool {
-        element != self.repo_name
+impl Rule {
+    pub fn new(action: Action, pattern: RepoPattern) -> Self {
+        Rule { action, pattern }
+    }
+
+    /// Parse a rule of the form `allow PATTERN` or `deny PATTERN`
+    pub fn parse(s: &str, config: &Configuration) -> Result<Self> {
+        let s = s.trim();
+        let (action, pattern) = match s.split_once(char::is_whitespace) {
+            Some(("allow", pattern)) => (Action::Allow, pattern),
+            Some(("deny", pattern)) => (Action::Deny, pattern),
+            _ => {
+                return Err(format_err!(
+                    "Invalid repository rule '{}', expected 'allow PATTERN' or 'deny PATTERN'",
+                    s
+                ))
+            }
+        };
+
+        RepoPattern::parse(pattern.trim(), config).map(|pattern| Rule::new(action, pattern))
     }
 }
 
-struct WhiteListFilter {
-    repo_name: String,
+/// Filter for repositories, built from ordered allow and deny rules
+#[derive(Debug)]
+pub struct RepoFilter {
+    rules: Vec<Rule>,
 }
 
-impl Filter<String> for WhiteListFilter {
-    fn filter(&self, element: &String) -> bool {
-        element == self.repo_name
+impl RepoFilter {
+    pub fn new(rules: Vec<Rule>) -> Self {
+        RepoFilter { rules }
+    }
+
+    /// Build the filter from the rules in the configuration
+    pub fn from_config(config: &Configuration) -> Result<Self> {
+        let rules = config
+            .repository_rules()
+            .iter()
+            .map(|rule| Rule::parse(rule, config))
+            .collect::<Result<Vec<_>>>()
+            .context("Parsing repository rules from configuration")
+            .map_err(|e| Error::from(UsageError::new(format!("{:#}", e))))?;
+
+        let legacy = config
+            .blacklist()
+            .iter()
+            .map(|repo| (Action::Deny, repo))
+            .chain(config.whitelist().iter().map(|repo| (Action::Allow, repo)))
+            .map(|(action, repo)| {
+                let repos = config.resolve_repository(repo);
+                Rule::new(action, RepoPattern::Repos(repos))
+            });
+
+        Ok(RepoFilter::new(rules.into_iter().chain(legacy).collect()))
+    }
+
+    /// Build the filter from the `--allow-repo` and `--deny-repo` arguments, if any are given
+    ///
+    /// Subcommands which do not have these arguments never override the rules.
+    pub fn from_matches(mtch: &ArgMatches, config: &Configuration) -> Result<Option<Self>> {
+        let rules_of = |id: &str, action: Action| {
+            if !mtch.try_contains_id(id).unwrap_or(false) {
+                return Vec::new();
+            }
+
+            mtch.indices_of(id)
+                .into_iter()
+                .flatten()
+                .zip(mtch.get_many::<String>(id).into_iter().flatten())
+                .map(move |(index, pattern)| (index, action, pattern))
+                .collect::<Vec<_>>()
+        };
+
+        let mut args = rules_of("allow-repo", Action::Allow);
+        args.extend(rules_of("deny-repo", Action::Deny));
+        if args.is_empty() {
+            return Ok(None);
+        }
+
+        args.sort_by_key(|(index, _, _)| *index);
+        args.into_iter()
+            .map(|(_, action, pattern)| {
+                RepoPattern::parse(pattern, config)
+                    .map(|pattern| Rule::new(action, pattern))
+                    .map_err(|e| Error::from(UsageError::new(format!("{:#}", e))))
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(|rules| Some(RepoFilter::new(rules)))
     }
 }
 
-pub fn repo_filter(config: &Configuration) -> Box<Filter<String>> {
-    let blacklist = config
-        .blacklist()
-        .iter()
-        .cloned()
-        .map(BlackListFilter::new)
-        .fold(Box::new(Bool::new(true)), |accu, element| accu.and(element));
-    let whitelist = config
-        .whitelist()
-        .iter()
-        .cloned()
-        .map(WhiteListFilter::new)
-        .fold(Box::new(Bool::new(true)), |accu, element| accu.and(element));
+impl Filter<Repo> for RepoFilter {
+    fn filter(&self, repo: &Repo) -> bool {
+        match self.rules.iter().find(|rule| rule.pattern.matches(repo)) {
+            Some(rule) => {
+                trace!("{:?} matched by {:?}", repo, rule);
+                rule.action == Action::Allow
+            }
+            None => {
+                let allowed = !self.rules.iter().any(|rule| rule.action == Action::Allow);
+                trace!("{:?} matched by no rule -> {}", repo, allowed);
+                allowed
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use filters::filter::Filter;
+    use librepology::v1::types::Repo;
+
+    use super::RepoFilter;
+    use super::Rule;
+    use crate::config::Configuration;
+
+    fn repo_filter(rules: &[&str]) -> RepoFilter {
+        let config = Configuration::default();
+        RepoFilter::new(
+            rules
+                .iter()
+                .map(|rule| Rule::parse(rule, &config).unwrap())
+                .collect(),
+        )
+    }
+
+    fn allowed(filter: &RepoFilter, repo: &str) -> bool {
+        filter.filter(&Repo::new(repo.to_string()))
+    }
+
+    #[test]
+    fn test_no_rules() {
+        assert!(allowed(&repo_filter(&[]), "debian_12"));
+    }
 
-    Box::new(blacklist.not().or(whitelist))
+    #[test]
+    fn test_first_match_wins() {
+        let filter = repo_filter(&["deny debian_11", "allow debian_*"]);
+        assert!(!allowed(&filter, "debian_11"));
+        assert!(allowed(&filter, "debian_12"));
+
+        let filter = repo_filter(&["allow debian_*", "deny debian_11"]);
+        assert!(allowed(&filter, "debian_11"));
+    }
+
+    #[test]
+    fn test_default() {
+        // Without allow rules, unmatched repositories are allowed
+        let filter = repo_filter(&["deny arch"]);
+        assert!(!allowed(&filter, "arch"));
+        assert!(allowed(&filter, "fedora_40"));
+
+        // With any allow rule, unmatched repositories are denied
+        let filter = repo_filter(&["deny arch", "allow debian_*"]);
+        assert!(!allowed(&filter, "arch"));
+        assert!(allowed(&filter, "debian_12"));
+        assert!(!allowed(&filter, "fedora_40"));
+    }
+
+    #[test]
+    fn test_regex() {
+        let filter = repo_filter(&[r"allow /^fedora_\d+$/"]);
+        assert!(allowed(&filter, "fedora_40"));
+        assert!(!allowed(&filter, "fedora_rawhide"));
+
+        // Regular expressions are not anchored implicitly
+        let filter = repo_filter(&["deny /bsd/"]);
+        assert!(!allowed(&filter, "freebsd"));
+        assert!(!allowed(&filter, "openbsd"));
+        assert!(allowed(&filter, "debian_12"));
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let config = Configuration::default();
+        assert!(Rule::parse("permit debian_12", &config).is_err());
+        assert!(Rule::parse("allow", &config).is_err());
+        assert!(Rule::parse("allow /(/", &config).is_err());
+    }
 }
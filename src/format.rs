@@ -0,0 +1,488 @@
+//! User-defined line formats and columns
+//!
+//! The `lines` output prints packages and problems with a format passed with `--format` or
+//! configured per subcommand in the `[formats]` table of the configuration:
+//!
+//! ```toml
+//! [formats]
+//! project = "{repo:<15} {version:>10} {status|no status}"
+//! problems = "{project}\t{type}"
+//! ```
+//!
+//! A placeholder has the form `{FIELD[/SEPARATOR][:[ALIGN]WIDTH][|DEFAULT]}`:
+//!
+//! * `FIELD` is one of `PACKAGE_FIELDS` or `PROBLEM_FIELDS`
+//! * `SEPARATOR` joins the values of fields with more than one value (licenses, maintainers, www,
+//!   downloads), `", "` by default
+//! * `ALIGN` is `<` (left, the default), `>` (right) or `^` (center), `WIDTH` the minimal width
+//! * `DEFAULT` is printed if the field has no value
+//!
+//! `\t` and `\n` are a tab and a newline, `\{`, `\}` and `\\` are literal braces and backslashes.
+//! In placeholders, `\/`, `\:` and `\|` are literal characters as well, so that they can be used
+//! in separators and defaults, for example `{licenses/ \| |none}`.
+//!
+//! The `table`, `lines`, `csv`, `tsv` and `markdown` outputs can show a selection of the same
+//! fields instead, passed with `--columns` or configured per subcommand:
+//!
+//! ```toml
+//! [columns]
+//! project = ["name", "repo", "version", "maintainers", "licenses"]
+//! ```
+
+use std::iter::Peekable;
+use std::ops::Deref;
+use std::str::Chars;
+
+use anyhow::Error;
+use anyhow::Result;
+use librepology::v1::types::Package;
+use librepology::v1::types::Problem;
+
+use crate::exitcode::UsageError;
+use crate::mapping::LocalNames;
+
+/// The fields of packages which can be used in a format
+pub const PACKAGE_FIELDS: &[&str] = &[
+    "project",
+    "local_name",
+    "repo",
+    "name",
+    "srcname",
+    "binname",
+    "visiblename",
+    "version",
+    "status",
+    "vulnerable",
+    "summary",
+    "licenses",
+    "maintainers",
+    "www",
+    "downloads",
+];
+
+/// The fields of problems which can be used in a format
+pub const PROBLEM_FIELDS: &[&str] = &["project", "maintainer", "type", "srcname", "version"];
+
+/// The fields which can be used in the format for the subcommand `command`, if it supports one
+pub fn fields(command: &str) -> Option<&'static [&'static str]> {
+    match command {
+        "project" => Some(PACKAGE_FIELDS),
+        "problems" => Some(PROBLEM_FIELDS),
+        _ => None,
+    }
+}
+
+/// A parsed line format
+#[derive(Debug, Clone)]
+pub struct LineFormat(Vec<Part>);
+
+#[derive(Debug, Clone)]
+enum Part {
+    Text(String),
+    Field(Placeholder),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Debug, Clone)]
+struct Placeholder {
+    field: String,
+    separator: String,
+    align: Align,
+    width: Option<usize>,
+    default: String,
+}
+
+impl LineFormat {
+    /// Parse `format`, which may only use placeholders for `fields`
+    pub fn parse(format: &str, fields: &[&str]) -> Result<Self> {
+        let invalid = |reason: String| {
+            Error::from(UsageError::new(format!(
+                "Invalid format '{}': {}",
+                format, reason
+            )))
+        };
+
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = format.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('t') => text.push('\t'),
+                    Some('n') => text.push('\n'),
+                    Some(other) => text.push(other),
+                    None => text.push('\\'),
+                },
+
+                '{' => {
+                    // Escapes are kept, they are resolved when parsing the placeholder
+                    let mut spec = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some('\\') => {
+                                spec.push('\\');
+                                spec.extend(chars.next());
+                            }
+                            Some(c) => spec.push(c),
+                            None => return Err(invalid(String::from("unclosed '{'"))),
+                        }
+                    }
+
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Field(
+                        Placeholder::parse(&spec, fields).map_err(invalid)?,
+                    ));
+                }
+
+                '}' => return Err(invalid(String::from("unmatched '}', use '\\}'"))),
+                c => text.push(c),
+            }
+        }
+
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(LineFormat(parts))
+    }
+
+    /// Render the format, with `values` returning the values of a field
+    pub fn render<F>(&self, values: F) -> String
+    where
+        F: Fn(&str) -> Vec<String>,
+    {
+        self.0
+            .iter()
+            .map(|part| match part {
+                Part::Text(text) => text.clone(),
+                Part::Field(placeholder) => placeholder.render(values(&placeholder.field)),
+            })
+            .collect()
+    }
+
+    /// Render the format for a package
+    pub fn package(&self, package: &Package, local_names: &LocalNames) -> String {
+        self.render(|field| package_values(package, local_names, field))
+    }
+
+    /// Render the format for a problem
+    pub fn problem(&self, problem: &Problem) -> String {
+        self.render(|field| problem_values(problem, field))
+    }
+}
+
+/// A selection of fields, shown as columns in the given order
+#[derive(Debug, Clone)]
+pub struct Columns(Vec<String>);
+
+impl Columns {
+    /// Parse the column names, which have to be in `fields`
+    pub fn parse<S: AsRef<str>>(columns: &[S], fields: &[&str]) -> Result<Self> {
+        columns
+            .iter()
+            .map(|column| column.as_ref().trim())
+            .map(|column| {
+                if fields.contains(&column) {
+                    Ok(column.to_string())
+                } else {
+                    Err(Error::from(UsageError::new(format!(
+                        "Unknown column '{}', known columns are: {}",
+                        column,
+                        fields.join(", ")
+                    ))))
+                }
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(Columns)
+    }
+
+    /// All of `fields`, in their order
+    pub fn all(fields: &[&str]) -> Self {
+        Columns(fields.iter().map(|field| field.to_string()).collect())
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.0
+    }
+
+    /// The column titles for human-readable outputs, "local_name" becomes "Local name"
+    pub fn titles(&self) -> Vec<String> {
+        self.0
+            .iter()
+            .map(|name| {
+                let mut chars = name.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                    .unwrap_or_default()
+                    .replace('_', " ")
+            })
+            .collect()
+    }
+
+    /// The values of the columns for a package, one list of values per column
+    pub fn package(&self, package: &Package, local_names: &LocalNames) -> Vec<Vec<String>> {
+        self.0
+            .iter()
+            .map(|column| package_values(package, local_names, column))
+            .collect()
+    }
+
+    /// The values of the columns for a problem, one list of values per column
+    pub fn problem(&self, problem: &Problem) -> Vec<Vec<String>> {
+        self.0
+            .iter()
+            .map(|column| problem_values(problem, column))
+            .collect()
+    }
+}
+
+/// Helper for reading a part of a placeholder up to the first unescaped character of `stops`,
+/// resolving escapes
+fn take_until(chars: &mut Peekable<Chars>, stops: &[char]) -> String {
+    let mut part = String::new();
+    while let Some(c) = chars.next_if(|c| !stops.contains(c)) {
+        match c {
+            '\\' => match chars.next() {
+                Some('t') => part.push('\t'),
+                Some('n') => part.push('\n'),
+                Some(other) => part.push(other),
+                None => part.push('\\'),
+            },
+            c => part.push(c),
+        }
+    }
+    part
+}
+
+impl Placeholder {
+    /// Parse the placeholder `{FIELD[/SEPARATOR][:[ALIGN]WIDTH][|DEFAULT]}`, without the braces
+    fn parse(spec: &str, fields: &[&str]) -> std::result::Result<Self, String> {
+        let mut chars = spec.chars().peekable();
+
+        let field = take_until(&mut chars, &['/', ':', '|']);
+        let field = field.trim();
+        if !fields.contains(&field) {
+            return Err(format!(
+                "unknown field '{}', known fields are: {}",
+                field,
+                fields.join(", ")
+            ));
+        }
+
+        let separator = match chars.next_if_eq(&'/') {
+            Some(_) => take_until(&mut chars, &[':', '|']),
+            None => String::from(", "),
+        };
+        let layout = match chars.next_if_eq(&':') {
+            Some(_) => take_until(&mut chars, &['/', ':', '|']),
+            None => String::new(),
+        };
+        let default = match chars.next_if_eq(&'|') {
+            Some(_) => take_until(&mut chars, &[]),
+            None => String::new(),
+        };
+
+        if chars.peek().is_some() {
+            return Err(format!(
+                "unexpected '{}' in the placeholder of field '{}', escape '/', ':' and '|' with '\\'",
+                chars.collect::<String>(),
+                field
+            ));
+        }
+
+        let (align, width) = match layout.chars().next() {
+            Some('<') => (Align::Left, &layout[1..]),
+            Some('>') => (Align::Right, &layout[1..]),
+            Some('^') => (Align::Center, &layout[1..]),
+            _ => (Align::Left, layout.as_str()),
+        };
+        let width = if width.is_empty() {
+            None
+        } else {
+            Some(
+                width
+                    .parse::<usize>()
+                    .map_err(|_| format!("invalid width '{}' for field '{}'", width, field))?,
+            )
+        };
+
+        Ok(Placeholder {
+            field: field.to_string(),
+            separator,
+            align,
+            width,
+            default,
+        })
+    }
+
+    fn render(&self, values: Vec<String>) -> String {
+        let value = if values.is_empty() {
+            self.default.clone()
+        } else {
+            values.join(&self.separator)
+        };
+
+        match (self.width, self.align) {
+            (None, _) => value,
+            (Some(width), Align::Left) => format!("{:<width$}", value, width = width),
+            (Some(width), Align::Right) => format!("{:>width$}", value, width = width),
+            (Some(width), Align::Center) => format!("{:^width$}", value, width = width),
+        }
+    }
+}
+
+/// Helper for turning optional and multi-value fields into a list of values
+fn values<'a, I, T>(values: I) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a T>,
+    T: Deref<Target = String> + 'a,
+{
+    values
+        .into_iter()
+        .map(|value| value.deref().clone())
+        .collect()
+}
+
+/// The values of the package field `field`, empty if the package has no value for it
+fn package_values(package: &Package, local_names: &LocalNames, field: &str) -> Vec<String> {
+    match field {
+        "project" => values(package.effname()),
+        "local_name" => vec![crate::mapping::local_name(local_names, package).to_string()],
+        "repo" => values(Some(package.repo())),
+        "name" => values(package.name()),
+        "srcname" => values(package.srcname()),
+        "binname" => values(package.binname()),
+        "visiblename" => values(package.visiblename()),
+        "version" => values(Some(package.version())),
+        "status" => package
+            .status()
+            .map(|s| s.to_string())
+            .into_iter()
+            .collect(),
+        "vulnerable" => vec![package.is_vulnerable().to_string()],
+        "summary" => values(package.summary()),
+        "licenses" => values(package.licenses().into_iter().flatten()),
+        "maintainers" => values(package.maintainers().into_iter().flatten()),
+        "www" => package
+            .www()
+            .into_iter()
+            .flatten()
+            .map(|url| url.as_str().to_string())
+            .collect(),
+        "downloads" => package
+            .downloads()
+            .into_iter()
+            .flatten()
+            .map(|url| url.as_str().to_string())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The values of the problem field `field`, empty if the problem has no value for it
+fn problem_values(problem: &Problem, field: &str) -> Vec<String> {
+    match field {
+        "project" => values(Some(problem.project_name())),
+        "maintainer" => values(Some(problem.maintainer())),
+        "type" => vec![problem.problem_type().clone()],
+        "srcname" => problem.srcname().cloned().into_iter().collect(),
+        "version" => values(problem.version()),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LineFormat;
+    use super::PACKAGE_FIELDS;
+
+    /// Render `format` with the given values per field
+    fn render(format: &str, values: &[(&str, &[&str])]) -> String {
+        LineFormat::parse(format, PACKAGE_FIELDS)
+            .unwrap()
+            .render(|field| {
+                values
+                    .iter()
+                    .find(|(name, _)| *name == field)
+                    .map(|(_, values)| values.iter().map(|v| v.to_string()).collect())
+                    .unwrap_or_default()
+            })
+    }
+
+    fn error(format: &str) -> String {
+        LineFormat::parse(format, PACKAGE_FIELDS)
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn test_text_and_fields() {
+        let values: &[(&str, &[&str])] = &[("repo", &["debian_12"]), ("version", &["1.0"])];
+        assert_eq!(render("{repo}: {version}", values), "debian_12: 1.0");
+        assert_eq!(render("{ repo }", values), "debian_12");
+        assert_eq!(render("\\{{repo}\\}\\t\\\\", values), "{debian_12}\t\\");
+    }
+
+    #[test]
+    fn test_separator() {
+        let values: &[(&str, &[&str])] = &[("licenses", &["MIT", "GPL"])];
+        assert_eq!(render("{licenses}", values), "MIT, GPL");
+        assert_eq!(render("{licenses/ + }", values), "MIT + GPL");
+        assert_eq!(render("{licenses/a/b}", values), "MITa/bGPL");
+        assert_eq!(render("{licenses/\\:}", values), "MIT:GPL");
+        assert_eq!(render("{licenses/ \\| }", values), "MIT | GPL");
+        assert_eq!(render("{licenses/\\t}", values), "MIT\tGPL");
+    }
+
+    #[test]
+    fn test_layout() {
+        let values: &[(&str, &[&str])] = &[("version", &["1.0"])];
+        assert_eq!(render("{version:5}", values), "1.0  ");
+        assert_eq!(render("{version:<5}", values), "1.0  ");
+        assert_eq!(render("{version:>5}", values), "  1.0");
+        assert_eq!(render("{version:^5}", values), " 1.0 ");
+        assert_eq!(render("{version:}", values), "1.0");
+        assert_eq!(render("{version:>}", values), "1.0");
+    }
+
+    #[test]
+    fn test_default() {
+        let values: &[(&str, &[&str])] = &[("licenses", &["MIT", "GPL"])];
+        assert_eq!(render("{status|no status}", values), "no status");
+        assert_eq!(render("{status|a:b/c|d}", values), "a:b/c|d");
+        assert_eq!(render("{status:>4|-}", values), "   -");
+        assert_eq!(render("{licenses/ \\| |none}", values), "MIT | GPL");
+        assert_eq!(render("{status/ \\| |none}", values), "none");
+    }
+
+    #[test]
+    fn test_all_parts() {
+        let values: &[(&str, &[&str])] = &[("maintainers", &["a", "b"])];
+        assert_eq!(render("{maintainers/\\::>5|-}", values), "  a:b");
+        assert_eq!(render("{www/\\::>5|-}", values), "    -");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(error("{repo").contains("unclosed '{'"));
+        assert!(error("repo}").contains("unmatched '}'"));
+        assert!(error("{nope}").contains("unknown field 'nope'"));
+        assert!(error("{version:x}").contains("invalid width 'x'"));
+    }
+
+    #[test]
+    fn test_leftovers() {
+        assert!(error("{version:10/x}").contains("unexpected '/x'"));
+        assert!(error("{version:10:x}").contains("unexpected ':x'"));
+        assert!(error("{licenses/x:10/y}").contains("unexpected '/y'"));
+    }
+}
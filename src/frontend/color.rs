@@ -0,0 +1,182 @@
+//! Colors of the terminal outputs
+//!
+//! The `lines` and `table` outputs color packages by their status, vulnerable packages are printed
+//! bold in their own color. The colors can be changed in the `[colors]` table of the
+//! configuration, by status:
+//!
+//! ```toml
+//! [colors]
+//! outdated = "bright_red"
+//! vulnerable = "magenta"
+//! rolling = "none"
+//! ```
+//!
+//! Colors are used if stdout is a terminal and `NO_COLOR` is not set, or if `--color always` is
+//! passed.
+
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+
+use anyhow::Error;
+use anyhow::Result;
+use clap::ArgMatches;
+use librepology::v1::types::Status;
+
+use crate::exitcode::UsageError;
+
+/// The names of the colors, in the order of the terminal color numbers
+const COLORS: &[&str] = &[
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "magenta",
+    "cyan",
+    "white",
+    "bright_black",
+    "bright_red",
+    "bright_green",
+    "bright_yellow",
+    "bright_blue",
+    "bright_magenta",
+    "bright_cyan",
+    "bright_white",
+];
+
+/// The keys of the theme with their default colors, "none" for no color
+const DEFAULT_THEME: &[(&str, &str)] = &[
+    ("newest", "green"),
+    ("devel", "cyan"),
+    ("unique", "blue"),
+    ("outdated", "red"),
+    ("legacy", "yellow"),
+    ("rolling", "cyan"),
+    ("noscheme", "none"),
+    ("incorrect", "magenta"),
+    ("untrusted", "magenta"),
+    ("ignored", "none"),
+    ("vulnerable", "bright_red"),
+];
+
+/// Whether the terminal outputs should be colored, following `--color` and `NO_COLOR`
+pub fn enabled(app: &ArgMatches) -> bool {
+    match app.get_one::<String>("color").map(String::as_str) {
+        Some("always") => true,
+        Some("never") => false,
+        _ => {
+            let no_color = std::env::var_os("NO_COLOR")
+                .map(|value| !value.is_empty())
+                .unwrap_or(false);
+            !no_color && std::io::stdout().is_terminal()
+        }
+    }
+}
+
+/// A terminal color, by its number
+#[derive(Clone, Copy, Debug)]
+struct Color(u32);
+
+impl Color {
+    /// Parse a color name, `None` for "none"
+    fn parse(name: &str) -> Option<Option<Color>> {
+        if name == "none" {
+            return Some(None);
+        }
+
+        COLORS
+            .iter()
+            .position(|color| *color == name)
+            .map(|number| Some(Color(number as u32)))
+    }
+
+    /// The ANSI SGR code of the color
+    fn ansi(self) -> u32 {
+        if self.0 < 8 {
+            30 + self.0
+        } else {
+            90 + self.0 - 8
+        }
+    }
+}
+
+/// The colors for the package statuses and for vulnerable packages
+#[derive(Clone, Debug)]
+pub struct Theme(BTreeMap<String, Option<Color>>);
+
+impl Theme {
+    /// The default theme, changed by `colors` from the configuration
+    pub fn new(colors: &BTreeMap<String, String>) -> Result<Self> {
+        let invalid = |message: String| Error::from(UsageError::new(message));
+
+        let mut theme = BTreeMap::new();
+        for (key, color) in DEFAULT_THEME.iter().copied() {
+            theme.insert(key.to_string(), Color::parse(color).flatten());
+        }
+
+        for (key, color) in colors {
+            if !theme.contains_key(key) {
+                return Err(invalid(format!(
+                    "Unknown key 'colors.{}', known keys are: {}",
+                    key,
+                    DEFAULT_THEME
+                        .iter()
+                        .map(|(key, _)| *key)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )));
+            }
+
+            let color = Color::parse(color).ok_or_else(|| {
+                invalid(format!(
+                    "Unknown color '{}' for 'colors.{}', known colors are: none, {}",
+                    color,
+                    key,
+                    COLORS.join(", ")
+                ))
+            })?;
+            theme.insert(key.clone(), color);
+        }
+
+        Ok(Theme(theme))
+    }
+
+    /// The style of a package with `status`
+    pub fn style(&self, status: Option<&Status>, vulnerable: bool) -> Style {
+        let key = if vulnerable {
+            String::from("vulnerable")
+        } else {
+            status.map(Status::to_string).unwrap_or_default()
+        };
+
+        Style {
+            color: self.0.get(&key).copied().flatten(),
+            bold: vulnerable,
+        }
+    }
+}
+
+/// The color and emphasis of a package
+#[derive(Clone, Copy, Debug)]
+pub struct Style {
+    color: Option<Color>,
+    bold: bool,
+}
+
+impl Style {
+    /// Wrap `text` in ANSI escape codes
+    pub fn paint(&self, text: &str) -> String {
+        let codes = self
+            .color
+            .map(|color| color.ansi().to_string())
+            .into_iter()
+            .chain(Some(String::from("1")).filter(|_| self.bold))
+            .collect::<Vec<_>>();
+
+        if codes.is_empty() {
+            text.to_string()
+        } else {
+            format!("\x1b[{}m{}\x1b[0m", codes.join(";"), text)
+        }
+    }
+}
@@ -0,0 +1,271 @@
+use std::borrow::Cow;
+use std::io::Stdout;
+use std::io::StdoutLock;
+use std::ops::Deref;
+
+use anyhow::Error;
+use anyhow::Result;
+use librepology::v1::types::Package;
+use librepology::v1::types::Problem;
+
+use crate::diff::Newer;
+use crate::diff::RepoDiff;
+use crate::format::Columns;
+use crate::format::PACKAGE_FIELDS;
+use crate::format::PROBLEM_FIELDS;
+use crate::frontend::Frontend;
+use crate::mapping::LocalNames;
+use crate::policy::PolicyResult;
+use crate::relative::RelativeView;
+use crate::report::NewestVersions;
+use crate::report::Report;
+use crate::watch::Change;
+
+/// A Frontend that prints the data as comma (or otherwise) separated values
+///
+/// Every output starts with a header row, which does not change between invocations. Packages
+/// and problems have a column per field of `format::PACKAGE_FIELDS` and `format::PROBLEM_FIELDS`
+/// unless columns are selected. Fields are quoted if necessary, fields with more than one value
+/// (licenses, maintainers, URLs) are joined with spaces.
+pub struct CsvFrontend {
+    stdout: Stdout,
+    delimiter: u8,
+
+    /// The columns of packages and problems, if not all fields
+    columns: Option<Columns>,
+}
+
+impl CsvFrontend {
+    pub fn new(stdout: Stdout, delimiter: u8, columns: Option<Columns>) -> Self {
+        CsvFrontend {
+            stdout,
+            delimiter,
+            columns,
+        }
+    }
+
+    /// The columns of packages, the selected columns or all package fields
+    fn package_columns(&self) -> Cow<'_, Columns> {
+        self.columns
+            .as_ref()
+            .map(Cow::Borrowed)
+            .unwrap_or_else(|| Cow::Owned(Columns::all(PACKAGE_FIELDS)))
+    }
+
+    /// The columns of problems, the selected columns or all problem fields
+    fn problem_columns(&self) -> Cow<'_, Columns> {
+        self.columns
+            .as_ref()
+            .map(Cow::Borrowed)
+            .unwrap_or_else(|| Cow::Owned(Columns::all(PROBLEM_FIELDS)))
+    }
+
+    fn writer(&self) -> ::csv::Writer<StdoutLock<'_>> {
+        ::csv::WriterBuilder::new()
+            .delimiter(self.delimiter)
+            .from_writer(self.stdout.lock())
+    }
+
+    fn write<I, R>(&self, header: &[&str], records: I) -> Result<()>
+    where
+        I: IntoIterator<Item = R>,
+        R: IntoIterator,
+        R::Item: AsRef<[u8]>,
+    {
+        let mut writer = self.writer();
+        writer.write_record(header)?;
+        for record in records {
+            writer.write_record(record)?;
+        }
+        writer.flush().map_err(Error::from)
+    }
+}
+
+/// Helper for the header row of the columns
+fn columns_header(columns: &Columns) -> Vec<&str> {
+    columns.names().iter().map(String::as_str).collect()
+}
+
+/// Helper for joining the values of selected columns to a record
+fn columns_record(columns: Vec<Vec<String>>) -> Vec<String> {
+    columns.into_iter().map(|values| values.join(" ")).collect()
+}
+
+/// Helper for turning an optional field into a column
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+impl Frontend for CsvFrontend {
+    fn list_packages(
+        &self,
+        packages: Vec<Package>,
+        local_names: &LocalNames,
+        _newest: &NewestVersions,
+    ) -> Result<()> {
+        let columns = self.package_columns();
+        self.write(
+            &columns_header(&columns),
+            packages
+                .iter()
+                .map(|package| columns_record(columns.package(package, local_names))),
+        )
+    }
+
+    fn list_problems(&self, problems: Vec<Problem>) -> Result<()> {
+        let columns = self.problem_columns();
+        self.write(
+            &columns_header(&columns),
+            problems
+                .iter()
+                .map(|problem| columns_record(columns.problem(problem))),
+        )
+    }
+
+    fn list_repo_diff(&self, diff: RepoDiff) -> Result<()> {
+        let repo_a = diff.repo_a().deref();
+        let repo_b = diff.repo_b().deref();
+
+        let in_both = diff.in_both().iter().map(|entry| {
+            let newer = match entry.newer() {
+                Newer::RepoA => repo_a.as_str(),
+                Newer::RepoB => repo_b.as_str(),
+                Newer::Same => "same",
+            };
+
+            vec![
+                entry.project().to_string(),
+                entry.version_a().to_string(),
+                entry.version_b().to_string(),
+                newer.to_string(),
+            ]
+        });
+        let only_in_a = diff.only_in_a().iter().map(|entry| {
+            vec![
+                entry.project().to_string(),
+                entry.version().to_string(),
+                String::new(),
+                String::from("only_a"),
+            ]
+        });
+        let only_in_b = diff.only_in_b().iter().map(|entry| {
+            vec![
+                entry.project().to_string(),
+                String::new(),
+                entry.version().to_string(),
+                String::from("only_b"),
+            ]
+        });
+
+        self.write(
+            &["project", repo_a, repo_b, "newer"],
+            in_both.chain(only_in_a).chain(only_in_b),
+        )
+    }
+
+    fn list_relative(&self, view: RelativeView) -> Result<()> {
+        let columns = self.package_columns();
+        let mut header = columns_header(&columns);
+        header.extend(["relation", "reference", "reference_version"]);
+
+        self.write(
+            &header,
+            view.packages().iter().map(|relpkg| {
+                let mut record =
+                    columns_record(columns.package(relpkg.package(), &LocalNames::new()));
+                record.push(relpkg.relation().to_string());
+                record.push(view.reference_repo().to_string());
+                record.push(relpkg.reference_version().to_string());
+                record
+            }),
+        )
+    }
+
+    fn list_report(&self, report: Report) -> Result<()> {
+        let records = report.entries().iter().flat_map(|entry| {
+            let newest = optional(entry.newest().map(Deref::deref));
+
+            let pinned = entry.pinned().map(|pinned| {
+                vec![
+                    entry.project().to_string(),
+                    entry
+                        .local_name()
+                        .unwrap_or(entry.project().as_str())
+                        .to_string(),
+                    String::from("(pinned)"),
+                    pinned.version().to_string(),
+                    optional(pinned.relation()),
+                    String::new(),
+                    newest.clone(),
+                ]
+            });
+
+            let repositories = entry.repositories().iter().map(move |state| {
+                let status = match (state.version(), state.status()) {
+                    (None, _) => String::from("not packaged"),
+                    (Some(_), status) => optional(status),
+                };
+
+                vec![
+                    entry.project().to_string(),
+                    entry
+                        .local_name()
+                        .unwrap_or(entry.project().as_str())
+                        .to_string(),
+                    state.repo().to_string(),
+                    optional(state.version().map(Deref::deref)),
+                    status,
+                    state.is_vulnerable().to_string(),
+                    newest.clone(),
+                ]
+            });
+
+            pinned.into_iter().chain(repositories)
+        });
+
+        self.write(
+            &[
+                "project",
+                "local_name",
+                "repo",
+                "version",
+                "freshness",
+                "vulnerable",
+                "newest",
+            ],
+            records,
+        )
+    }
+
+    fn list_changes(&self, changes: Vec<Change>) -> Result<()> {
+        self.write(
+            &["project", "local_name", "repo", "change"],
+            changes.iter().map(|change| {
+                vec![
+                    change.project().to_string(),
+                    change.local_name().unwrap_or(change.project()).to_string(),
+                    change.repo().to_string(),
+                    change.description(),
+                ]
+            }),
+        )
+    }
+
+    fn list_violations(&self, result: PolicyResult) -> Result<()> {
+        self.write(
+            &["rule", "project", "local_name", "repo", "message"],
+            result.violations().iter().map(|violation| {
+                vec![
+                    violation.rule().to_string(),
+                    violation.project().to_string(),
+                    violation
+                        .local_name()
+                        .unwrap_or(violation.project())
+                        .to_string(),
+                    optional(violation.repo().map(Deref::deref)),
+                    violation.message().to_string(),
+                ]
+            }),
+        )
+    }
+}
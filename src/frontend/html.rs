@@ -0,0 +1,156 @@
+use std::io::Stdout;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::Context;
+use anyhow::Error;
+use anyhow::Result;
+use handlebars::Handlebars;
+use librepology::v1::types::Package;
+use librepology::v1::types::Problem;
+use librepology::v1::types::Repo;
+use serde::Serialize;
+
+use crate::diff::RepoDiff;
+use crate::frontend::Frontend;
+use crate::frontend::MappedPackage;
+use crate::mapping::LocalNames;
+use crate::policy::PolicyResult;
+use crate::relative::RelativeView;
+use crate::report::NewestVersions;
+use crate::report::Report;
+use crate::watch::Change;
+
+/// The built-in templates, by name
+const TEMPLATES: &[(&str, &str)] = &[
+    ("project", include_str!("../../etc/project.html")),
+    ("problems", include_str!("../../etc/problems.html")),
+    ("compare", include_str!("../../etc/compare.html")),
+    ("diff", include_str!("../../etc/diff.html")),
+    ("report", include_str!("../../etc/report.html")),
+    ("changes", include_str!("../../etc/changes.html")),
+    ("policy", include_str!("../../etc/policy.html")),
+];
+
+/// The stylesheet embedded into the pages, available to templates as the `style` partial
+const STYLE: &str = include_str!("../../etc/repolocli.css");
+
+/// The name under which a template passed with `--template` is registered
+const CUSTOM_TEMPLATE: &str = "custom";
+
+/// A Frontend that renders the data as HTML page with handlebars templates
+///
+/// The templates get the same data as the JSON frontend prints (except for changes, which are
+/// rendered with their description), so templates written for the JSON output keep working. The
+/// stylesheet is embedded with `{{> style}}`, so the pages do not need network access.
+pub struct HtmlFrontend {
+    stdout: Stdout,
+    handlebars: Handlebars<'static>,
+    custom: bool,
+}
+
+impl HtmlFrontend {
+    /// Create a HTML frontend, which renders everything with the template at `template`, if given
+    pub fn new(stdout: Stdout, template: Option<&Path>) -> Result<Self> {
+        let mut handlebars = Handlebars::new();
+        handlebars
+            .register_partial("style", format!("<style>\n{}</style>", STYLE))
+            .context("Registering stylesheet")?;
+
+        for (name, template) in TEMPLATES {
+            handlebars
+                .register_template_string(name, template)
+                .with_context(|| format!("Registering built-in template '{}'", name))?;
+        }
+
+        if let Some(path) = template {
+            debug!("Registering template from {}", path.display());
+            handlebars
+                .register_template_file(CUSTOM_TEMPLATE, path)
+                .with_context(|| format!("Reading template from {}", path.display()))?;
+        }
+
+        Ok(HtmlFrontend {
+            stdout,
+            handlebars,
+            custom: template.is_some(),
+        })
+    }
+
+    fn render<T: Serialize>(&self, template: &str, data: &T) -> Result<()> {
+        let template = if self.custom {
+            CUSTOM_TEMPLATE
+        } else {
+            template
+        };
+        trace!("Rendering template '{}'", template);
+
+        let output = self
+            .handlebars
+            .render(template, data)
+            .with_context(|| format!("Rendering template '{}'", template))?;
+
+        let mut outlock = self.stdout.lock();
+        writeln!(outlock, "{}", output).map_err(Error::from)
+    }
+}
+
+impl Frontend for HtmlFrontend {
+    fn list_packages(
+        &self,
+        packages: Vec<Package>,
+        local_names: &LocalNames,
+        _newest: &NewestVersions,
+    ) -> Result<()> {
+        if local_names.is_empty() {
+            return self.render("project", &packages);
+        }
+
+        let packages = packages
+            .iter()
+            .map(|package| MappedPackage::new(package, local_names))
+            .collect::<Vec<_>>();
+        self.render("project", &packages)
+    }
+
+    fn list_problems(&self, problems: Vec<Problem>) -> Result<()> {
+        self.render("problems", &problems)
+    }
+
+    fn list_repo_diff(&self, diff: RepoDiff) -> Result<()> {
+        self.render("diff", &diff)
+    }
+
+    fn list_relative(&self, view: RelativeView) -> Result<()> {
+        self.render("compare", &view)
+    }
+
+    fn list_report(&self, report: Report) -> Result<()> {
+        self.render("report", &report)
+    }
+
+    fn list_changes(&self, changes: Vec<Change>) -> Result<()> {
+        #[derive(Serialize)]
+        struct DescribedChange<'a> {
+            project: &'a str,
+            local_name: Option<&'a str>,
+            repo: &'a Repo,
+            description: String,
+        }
+
+        let changes = changes
+            .iter()
+            .map(|change| DescribedChange {
+                project: change.project(),
+                local_name: change.local_name(),
+                repo: change.repo(),
+                description: change.description(),
+            })
+            .collect::<Vec<_>>();
+        self.render("changes", &changes)
+    }
+
+    fn list_violations(&self, result: PolicyResult) -> Result<()> {
+        self.render("policy", &result)
+    }
+}
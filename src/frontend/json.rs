@@ -3,39 +3,89 @@ use std::io::Write;
 
 use anyhow::Error;
 use anyhow::Result;
+use librepology::v1::envelope::Envelope;
 use librepology::v1::types::Package;
 use librepology::v1::types::Problem;
+use serde::Serialize;
 
+use crate::diff::RepoDiff;
 use crate::frontend::Frontend;
-
-pub struct JsonFrontend(Stdout);
+use crate::frontend::MappedPackage;
+use crate::mapping::LocalNames;
+use crate::policy::PolicyResult;
+use crate::relative::RelativeView;
+use crate::report::NewestVersions;
+use crate::report::Report;
+use crate::watch::Change;
 
 /// A Frontend that serializes the data to JSON
 ///
 /// Useful for piping the data as structured data to another program.
 ///
-/// # Warning
-///
-/// This frontend does _not_ maintain compatibility with repolocli itself. That means that piping
-/// output from repolocli to repolocli is _NOT_ supported by this frontend.
-///
+/// If an envelope is given, the data is wrapped in it, so that the output records the query, the
+/// time and the source it was fetched from. Enveloped packages and problems can be read again
+/// with `--stdin`, so that the output of one run can be filtered, sorted and rendered by another.
+pub struct JsonFrontend {
+    stdout: Stdout,
+    envelope: Option<Envelope<()>>,
+}
+
 impl JsonFrontend {
-    pub fn new(stdout: Stdout) -> Self {
-        JsonFrontend(stdout)
+    pub fn new(stdout: Stdout, envelope: Option<Envelope<()>>) -> Self {
+        JsonFrontend { stdout, envelope }
     }
 
-    fn write(&self, output: String) -> Result<()> {
-        let mut outlock = self.0.lock();
+    fn write<T: Serialize>(&self, data: &T) -> Result<()> {
+        let output = match self.envelope.as_ref() {
+            Some(envelope) => serde_json::ser::to_string_pretty(&envelope.with_data(data)),
+            None => serde_json::ser::to_string_pretty(data),
+        }
+        .map_err(Error::from)?;
+
+        let mut outlock = self.stdout.lock();
         writeln!(outlock, "{}", output).map_err(Error::from)
     }
 }
 
 impl Frontend for JsonFrontend {
-    fn list_packages(&self, packages: Vec<Package>) -> Result<()> {
-        self.write(serde_json::ser::to_string_pretty(&packages).map_err(Error::from)?)
+    fn list_packages(
+        &self,
+        packages: Vec<Package>,
+        local_names: &LocalNames,
+        _newest: &NewestVersions,
+    ) -> Result<()> {
+        if local_names.is_empty() {
+            return self.write(&packages);
+        }
+
+        let packages = packages
+            .iter()
+            .map(|package| MappedPackage::new(package, local_names))
+            .collect::<Vec<_>>();
+        self.write(&packages)
     }
 
     fn list_problems(&self, problems: Vec<Problem>) -> Result<()> {
-        self.write(serde_json::ser::to_string_pretty(&problems).map_err(Error::from)?)
+        self.write(&problems)
+    }
+
+    fn list_repo_diff(&self, diff: RepoDiff) -> Result<()> {
+        self.write(&diff)
+    }
+
+    fn list_relative(&self, view: RelativeView) -> Result<()> {
+        self.write(&view)
+    }
+
+    fn list_report(&self, report: Report) -> Result<()> {
+        self.write(&report)
+    }
+
+    fn list_changes(&self, changes: Vec<Change>) -> Result<()> {
+        self.write(&changes)
+    }
+
+    fn list_violations(&self, result: PolicyResult) -> Result<()> {
+        self.write(&result)
     }
 }
@@ -0,0 +1,127 @@
+//! Fitting the terminal outputs to the terminal
+//!
+//! The `lines` and `table` outputs size their columns to their content. If stdout is a terminal,
+//! low-priority columns (URLs, summaries, ...) are truncated (`lines`) or wrapped (`table`) first.
+//! If that is not enough, the widest of the remaining columns are shrunk as well, so that the
+//! output fits into the terminal. `--no-truncate` turns this off.
+
+use clap::ArgMatches;
+use terminal_size::Width;
+
+/// The columns which are shrunk first, by field name or (lowercase) table title
+const LOW_PRIORITY: &[&str] = &[
+    "url",
+    "www",
+    "downloads",
+    "summary",
+    "change",
+    "violation",
+    "maintainers",
+    "licenses",
+];
+
+/// The width low-priority columns are not shrunk below
+const MIN_WIDTH: usize = 10;
+
+/// The width of the terminal, if the output should be fitted into it
+pub fn terminal_width(app: &ArgMatches) -> Option<usize> {
+    if app.get_flag("no-truncate") {
+        return None;
+    }
+
+    // Only stdout matters, output piped to another program is not fitted
+    terminal_size::terminal_size_of(std::io::stdout()).map(|(Width(width), _)| width as usize)
+}
+
+/// The indices of the low-priority columns in `names`, in the order in which they are shrunk
+pub fn shrinkable<S: AsRef<str>>(names: &[S]) -> Vec<usize> {
+    LOW_PRIORITY
+        .iter()
+        .flat_map(|low| {
+            names
+                .iter()
+                .position(|name| name.as_ref().to_lowercase() == *low)
+        })
+        .collect()
+}
+
+/// The width of `text`, the width of its longest line
+pub fn width(text: &str) -> usize {
+    text.lines()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Shrink the columns at `shrinkable` (in this order) until all `widths` and the `overhead` of
+/// separators and borders fit into `available`
+///
+/// The shrinkable columns are not shrunk below `MIN_WIDTH`. If the columns still do not fit, the
+/// widest column is shrunk by one until they do, down to one character per column.
+pub fn fit(widths: &mut [usize], shrinkable: &[usize], overhead: usize, available: usize) {
+    let total = widths.iter().sum::<usize>() + overhead;
+    let mut excess = total.saturating_sub(available);
+
+    for column in shrinkable.iter().copied() {
+        if let Some(width) = widths.get_mut(column) {
+            let shrink = width.saturating_sub(MIN_WIDTH).min(excess);
+            *width -= shrink;
+            excess -= shrink;
+        }
+    }
+
+    while excess > 0 {
+        match widths
+            .iter_mut()
+            .filter(|width| **width > 1)
+            .max_by_key(|width| **width)
+        {
+            Some(width) => {
+                *width -= 1;
+                excess -= 1;
+            }
+            None => break,
+        }
+    }
+}
+
+/// Truncate `text` to `width` characters, marking the truncation with an ellipsis
+pub fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        text.to_string()
+    } else {
+        text.chars()
+            .take(width.saturating_sub(1))
+            .chain(std::iter::once('…'))
+            .collect()
+    }
+}
+
+/// Wrap `text` into lines of at most `width` characters, at whitespace where possible
+pub fn wrap(text: &str, width: usize) -> String {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let mut word = word.chars().collect::<Vec<_>>();
+        if !line.is_empty() && line.chars().count() + 1 + word.len() <= width {
+            line.push(' ');
+            line.extend(word);
+            continue;
+        }
+
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        while word.len() > width {
+            lines.push(word.drain(..width).collect::<String>());
+        }
+        line = word.into_iter().collect();
+    }
+
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines.join("\n")
+}
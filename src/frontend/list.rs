@@ -4,75 +4,420 @@ use std::ops::Deref;
 
 use anyhow::Error;
 use anyhow::Result;
+use itertools::Itertools;
+use librepology::v1::types::EffName;
 use librepology::v1::types::Name;
 use librepology::v1::types::Package;
 use librepology::v1::types::Problem;
+use librepology::v1::types::Status;
 
+use crate::diff::Newer;
+use crate::diff::RepoDiff;
+use crate::format::Columns;
+use crate::format::LineFormat;
+use crate::frontend::color::Theme;
+use crate::frontend::layout;
+use crate::frontend::with_vulnerable_marker;
 use crate::frontend::Frontend;
+use crate::mapping::LocalNames;
+use crate::policy::PolicyResult;
+use crate::relative::RelativeView;
+use crate::report::NewestVersions;
+use crate::report::Report;
+use crate::watch::Change;
 
-pub struct ListFrontend(Stdout);
+/// The separator between the values of a line
+const SEPARATOR: &str = " - ";
+
+/// The names of the values of a package line, for finding the low-priority ones
+const PACKAGE_NAMES: &[&str] = &["project", "name", "version", "repo", "status", "url"];
 
 /// A Frontend that prints the data in a human-readable way but without ASCII-art.
 ///
-/// It seperates the values with dashes ("-") for a slightly better reading experience.
+/// It seperates the values with dashes ("-") for a slightly better reading experience. Packages
+/// and problems can be printed with a user-defined format or selected columns instead, see
+/// `crate::format`.
+///
+/// The values are aligned to the longest value of each column. If a terminal width is given,
+/// URLs and other long values are truncated so that the lines fit into the terminal.
+pub struct ListFrontend {
+    stdout: Stdout,
+    format: Option<LineFormat>,
+    columns: Option<Columns>,
+
+    /// The colors for packages, if the output is colored
+    theme: Option<Theme>,
+
+    /// The width of the terminal, if the lines should fit into it
+    width: Option<usize>,
+}
+
 impl ListFrontend {
-    pub fn new(stdout: Stdout) -> Self {
-        ListFrontend(stdout)
+    pub fn new(
+        stdout: Stdout,
+        format: Option<LineFormat>,
+        columns: Option<Columns>,
+        theme: Option<Theme>,
+        width: Option<usize>,
+    ) -> Self {
+        ListFrontend {
+            stdout,
+            format,
+            columns,
+            theme,
+            width,
+        }
+    }
+
+    fn write<I: IntoIterator<Item = String>>(&self, lines: I) -> Result<()> {
+        let mut outlock = self.stdout.lock();
+        lines
+            .into_iter()
+            .try_fold((), |_, line| writeln!(outlock, "{}", line))
+            .map_err(Error::from)
+    }
+
+    /// Helper for coloring a line by the status of its package, if the output is colored
+    fn paint(&self, line: String, status: Option<&Status>, vulnerable: bool) -> String {
+        match self.theme.as_ref() {
+            Some(theme) => theme.style(status, vulnerable).paint(&line),
+            None => line,
+        }
     }
+
+    /// Lay out rows of values as lines, with each column as wide as its longest value
+    ///
+    /// If the lines should fit into the terminal, the columns at `shrinkable` are truncated.
+    fn layout(&self, rows: Vec<Vec<String>>, shrinkable: &[usize]) -> Vec<String> {
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut widths = (0..columns)
+            .map(|column| {
+                rows.iter()
+                    .filter_map(|row| row.get(column))
+                    .map(|value| layout::width(value))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(available) = self.width {
+            let overhead = SEPARATOR.len() * columns.saturating_sub(1);
+            layout::fit(&mut widths, shrinkable, overhead, available);
+        }
+
+        rows.into_iter()
+            .map(|row| {
+                let last = row.len().saturating_sub(1);
+                row.iter()
+                    .zip(widths.iter())
+                    .enumerate()
+                    .map(|(i, (value, width))| {
+                        let value = layout::truncate(value, *width);
+                        if i == last {
+                            value
+                        } else {
+                            format!("{:width$}", value, width = width)
+                        }
+                    })
+                    .join(SEPARATOR)
+            })
+            .collect()
+    }
+
+    /// The values of a package line, with the selected columns if there are any
+    fn package_values(&self, package: &Package, local_names: &LocalNames) -> Vec<String> {
+        match self.columns.as_ref() {
+            Some(columns) => columns
+                .package(package, local_names)
+                .into_iter()
+                .map(|values| values.join(", "))
+                .collect(),
+            None if local_names.is_empty() => package_values(package),
+            None => std::iter::once(crate::mapping::local_name(local_names, package).to_string())
+                .chain(package_values(package))
+                .collect(),
+        }
+    }
+
+    /// The indices of the package values which can be truncated
+    fn package_shrinkable(&self, local_names: &LocalNames) -> Vec<usize> {
+        match self.columns.as_ref() {
+            Some(columns) => layout::shrinkable(columns.names()),
+            None if local_names.is_empty() => layout::shrinkable(PACKAGE_NAMES),
+            None => layout::shrinkable(PACKAGE_NAMES)
+                .into_iter()
+                .map(|i| i + 1)
+                .collect(),
+        }
+    }
+
+    /// Format packages as lines, with the user-defined format if there is one
+    ///
+    /// The `extra` values are appended to the line of each package.
+    fn package_lines<'a, I>(&self, packages: I, local_names: &LocalNames) -> Vec<String>
+    where
+        I: IntoIterator<Item = (&'a Package, Vec<String>)>,
+    {
+        let (packages, extra): (Vec<_>, Vec<_>) = packages.into_iter().unzip();
+
+        let lines = match self.format.as_ref() {
+            Some(format) => packages
+                .iter()
+                .zip(extra)
+                .map(|(package, extra)| {
+                    std::iter::once(format.package(package, local_names))
+                        .chain(extra)
+                        .join(SEPARATOR)
+                })
+                .collect(),
+
+            None => {
+                let rows = packages
+                    .iter()
+                    .zip(extra)
+                    .map(|(package, extra)| {
+                        let mut values = self.package_values(package, local_names);
+                        values.extend(extra);
+                        values
+                    })
+                    .collect();
+                self.layout(rows, &self.package_shrinkable(local_names))
+            }
+        };
+
+        packages
+            .iter()
+            .zip(lines)
+            .map(|(package, line)| self.paint(line, package.status(), package.is_vulnerable()))
+            .collect()
+    }
+}
+
+/// Helper for the values of a package line, named by `PACKAGE_NAMES`
+fn package_values(package: &Package) -> Vec<String> {
+    let status = if let Some(stat) = package.status() {
+        stat.to_string()
+    } else {
+        String::from("No status")
+    }; // not optimal, but works for now.
+    let status = with_vulnerable_marker(status, package.is_vulnerable());
+
+    let url = if let Some(url) = package.www() {
+        if let Some(url) = url.first() {
+            url.deref().to_string()
+        } else {
+            String::from("")
+        }
+    } else {
+        String::from("")
+    }; // not optimal, but works for now
+
+    vec![
+        package
+            .effname()
+            .map(EffName::deref)
+            .cloned()
+            .unwrap_or_else(|| String::from("<unknown>")),
+        package
+            .any_name()
+            .map(Name::deref)
+            .cloned()
+            .unwrap_or_else(|| String::from("<unknown>")),
+        package.version().to_string(),
+        package.repo().to_string(),
+        status,
+        url,
+    ]
 }
 
 impl Frontend for ListFrontend {
-    fn list_packages(&self, packages: Vec<Package>) -> Result<()> {
-        let mut outlock = self.0.lock();
-
-        packages.iter().try_fold((), |_, package| {
-            let status = if let Some(stat) = package.status() {
-                stat.to_string()
-            } else {
-                String::from("No status")
-            }; // not optimal, but works for now.
-
-            let url = if let Some(url) = package.www() {
-                if let Some(url) = url.first() {
-                    url.deref().to_string()
-                } else {
-                    String::from("")
-                }
-            } else {
-                String::from("")
-            }; // not optimal, but works for now
-
-            writeln!(
-                outlock,
-                "{name:10} - {version:8} - {repo:15} - {status:5} - {www}",
-                name = package
-                    .any_name()
-                    .map(Name::deref)
-                    .map(String::deref)
-                    .unwrap_or_else(|| "<unknown>"),
-                version = package.version().deref(),
-                repo = package.repo().deref(),
-                status = status,
-                www = url
-            )
-            .map(|_| ())
-            .map_err(Error::from)
-        })
+    fn list_packages(
+        &self,
+        packages: Vec<Package>,
+        local_names: &LocalNames,
+        _newest: &NewestVersions,
+    ) -> Result<()> {
+        let lines = self.package_lines(
+            packages.iter().map(|package| (package, Vec::new())),
+            local_names,
+        );
+        self.write(lines)
     }
 
     fn list_problems(&self, problems: Vec<Problem>) -> Result<()> {
-        let mut outlock = self.0.lock();
-
-        problems.iter().try_fold((), |_, problem| {
-            writeln!(
-                outlock,
-                "{name:30} - {maintainer:30} - {ptype}",
-                name = problem.project_name().deref(),
-                maintainer = problem.maintainer().deref(),
-                ptype = problem.problem_type()
-            )
-            .map(|_| ())
-            .map_err(Error::from)
-        })
+        if let Some(format) = self.format.as_ref() {
+            return self.write(problems.iter().map(|problem| format.problem(problem)));
+        }
+
+        let (rows, shrinkable) = match self.columns.as_ref() {
+            Some(columns) => {
+                let rows = problems
+                    .iter()
+                    .map(|problem| {
+                        columns
+                            .problem(problem)
+                            .into_iter()
+                            .map(|values| values.join(", "))
+                            .collect()
+                    })
+                    .collect();
+                (rows, layout::shrinkable(columns.names()))
+            }
+
+            None => {
+                let rows = problems
+                    .iter()
+                    .map(|problem| {
+                        vec![
+                            problem.project_name().to_string(),
+                            problem.maintainer().to_string(),
+                            problem.problem_type().clone(),
+                        ]
+                    })
+                    .collect();
+                (rows, Vec::new())
+            }
+        };
+
+        self.write(self.layout(rows, &shrinkable))
+    }
+
+    fn list_repo_diff(&self, diff: RepoDiff) -> Result<()> {
+        let in_both = diff.in_both().iter().map(|entry| {
+            let newer = match entry.newer() {
+                Newer::RepoA => diff.repo_a().deref(),
+                Newer::RepoB => diff.repo_b().deref(),
+                Newer::Same => "same",
+            };
+
+            vec![
+                entry.project().to_string(),
+                entry.version_a().to_string(),
+                entry.version_b().to_string(),
+                newer.to_string(),
+            ]
+        });
+
+        let only_in_a = diff.only_in_a().iter().map(|e| (e, diff.repo_a()));
+        let only_in_b = diff.only_in_b().iter().map(|e| (e, diff.repo_b()));
+        let only_in = only_in_a.chain(only_in_b).map(|(entry, repo)| {
+            vec![
+                entry.project().to_string(),
+                entry.version().to_string(),
+                format!("only in {}", repo.deref()),
+            ]
+        });
+
+        self.write(self.layout(in_both.chain(only_in).collect(), &[]))
+    }
+
+    fn list_relative(&self, view: RelativeView) -> Result<()> {
+        let lines = self.package_lines(
+            view.packages().iter().map(|relpkg| {
+                let relation = format!(
+                    "{relation} (relative to {reference} {reference_version})",
+                    relation = relpkg.relation(),
+                    reference = view.reference_repo().deref(),
+                    reference_version = relpkg.reference_version().deref()
+                );
+                (relpkg.package(), vec![relation])
+            }),
+            &LocalNames::new(),
+        );
+        self.write(lines)
+    }
+
+    fn list_report(&self, report: Report) -> Result<()> {
+        let mut rows = Vec::new();
+        let mut styles = Vec::new();
+
+        report.entries().iter().for_each(|entry| {
+            let project = entry.display_name();
+            let newest = entry.newest().map(|v| v.deref().deref()).unwrap_or("?");
+            let newest = format!("newest: {}", newest);
+
+            if let Some(pinned) = entry.pinned() {
+                let relation = pinned
+                    .relation()
+                    .map(|r| r.to_string())
+                    .unwrap_or_else(|| String::from("unknown"));
+
+                rows.push(vec![
+                    project.clone(),
+                    String::from("(pinned)"),
+                    pinned.version().to_string(),
+                    relation,
+                    newest.clone(),
+                ]);
+                styles.push(None);
+            }
+
+            entry.repositories().iter().for_each(|state| {
+                let version = state.version().map(|v| v.deref().deref()).unwrap_or("-");
+                let status = match (state.version(), state.status()) {
+                    (None, _) => String::from("not packaged"),
+                    (Some(_), Some(status)) => status.to_string(),
+                    (Some(_), None) => String::from("No status"),
+                };
+                let status = with_vulnerable_marker(status, state.is_vulnerable());
+
+                rows.push(vec![
+                    project.clone(),
+                    state.repo().to_string(),
+                    version.to_string(),
+                    status,
+                    newest.clone(),
+                ]);
+                styles.push(Some((state.status(), state.is_vulnerable())));
+            });
+        });
+
+        let lines = self
+            .layout(rows, &[])
+            .into_iter()
+            .zip(styles)
+            .map(|(line, style)| match style {
+                Some((status, vulnerable)) => self.paint(line, status, vulnerable),
+                None => line,
+            })
+            .collect::<Vec<_>>();
+        self.write(lines)
+    }
+
+    fn list_changes(&self, changes: Vec<Change>) -> Result<()> {
+        let rows = changes
+            .iter()
+            .map(|change| {
+                vec![
+                    change.display_name(),
+                    change.repo().to_string(),
+                    change.description(),
+                ]
+            })
+            .collect();
+
+        self.write(self.layout(rows, &[2]))
+    }
+
+    fn list_violations(&self, result: PolicyResult) -> Result<()> {
+        let rows = result
+            .violations()
+            .iter()
+            .map(|violation| {
+                vec![
+                    violation.rule().to_string(),
+                    violation.display_name(),
+                    violation
+                        .repo()
+                        .map(|r| r.to_string())
+                        .unwrap_or_else(|| String::from("-")),
+                    violation.message().to_string(),
+                ]
+            })
+            .collect();
+
+        let mut lines = self.layout(rows, &[3]);
+        lines.push(format!("summary: {}", result.summary()));
+        self.write(lines)
     }
 }
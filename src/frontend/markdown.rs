@@ -0,0 +1,472 @@
+use std::io::Stdout;
+use std::io::Write;
+use std::ops::Deref;
+
+use anyhow::Error;
+use anyhow::Result;
+use itertools::Itertools;
+use librepology::v1::types::Name;
+use librepology::v1::types::Package;
+use librepology::v1::types::Problem;
+use librepology::v1::types::Status;
+use url::Url;
+
+use crate::diff::Newer;
+use crate::diff::RepoDiff;
+use crate::format::Columns;
+use crate::frontend::Frontend;
+use crate::mapping::LocalNames;
+use crate::policy::PolicyResult;
+use crate::relative::RelativeView;
+use crate::report::NewestVersions;
+use crate::report::Report;
+use crate::watch::Change;
+
+/// A Frontend that prints GitHub-flavoured markdown tables, for pasting into issues and wiki pages
+///
+/// Packages are grouped by project, with a heading and a table per project. Statuses are shown
+/// with an emoji, URLs as links. All text from repology is escaped, so that it cannot break the
+/// table.
+pub struct MarkdownFrontend {
+    stdout: Stdout,
+
+    /// The columns of package and problem tables, if not the default ones
+    columns: Option<Columns>,
+}
+
+impl MarkdownFrontend {
+    pub fn new(stdout: Stdout, columns: Option<Columns>) -> Self {
+        MarkdownFrontend { stdout, columns }
+    }
+
+    fn write(&self, output: String) -> Result<()> {
+        let mut outlock = self.stdout.lock();
+        write!(outlock, "{}", output).map_err(Error::from)
+    }
+
+    /// The header of package tables, the selected columns or `PACKAGE_HEADER`
+    fn package_header(&self) -> Vec<String> {
+        match self.columns.as_ref() {
+            Some(columns) => columns.titles(),
+            None => PACKAGE_HEADER
+                .iter()
+                .map(|title| title.to_string())
+                .collect(),
+        }
+    }
+
+    /// The cells of a package row, in the order of `package_header()`
+    fn package_cells(&self, package: &Package, local_names: &LocalNames) -> Vec<String> {
+        match self.columns.as_ref() {
+            Some(columns) => columns
+                .names()
+                .iter()
+                .zip(columns_cells(columns.package(package, local_names)))
+                .map(|(name, cell)| match name.as_str() {
+                    "version" => code(package.version()),
+                    "status" => badge(package.status()),
+                    "www" => links(package.www().into_iter().flatten().map(|www| www.as_str())),
+                    "downloads" => links(
+                        package
+                            .downloads()
+                            .into_iter()
+                            .flatten()
+                            .map(|download| download.as_str()),
+                    ),
+                    _ => cell,
+                })
+                .collect(),
+            None => package_cells(package),
+        }
+    }
+}
+
+/// Helper for the escaped cells of selected columns
+fn columns_cells(columns: Vec<Vec<String>>) -> Vec<String> {
+    columns
+        .iter()
+        .map(|values| escape(&values.join(", ")))
+        .collect()
+}
+
+/// Helper for escaping text so that it is shown verbatim in a table cell
+fn escape(text: &str) -> String {
+    text.split_whitespace()
+        .join(" ")
+        .chars()
+        .fold(String::new(), |mut escaped, c| {
+            if "\\`*_[]<>#|~".contains(c) {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+            escaped
+        })
+}
+
+/// Helper for showing a version as code
+///
+/// Pipes are escaped in code as well, as the table is split into cells before code is parsed.
+fn code(version: &str) -> String {
+    format!("`{}`", version.replace('`', "'").replace('|', "\\|"))
+}
+
+/// Helper for showing URLs as links, with the host as text
+fn links<'a>(urls: impl IntoIterator<Item = &'a str>) -> String {
+    urls.into_iter()
+        .map(|url| {
+            let host = Url::parse(url)
+                .ok()
+                .and_then(|parsed| parsed.host_str().map(String::from));
+            format!(
+                "[{}](<{}>)",
+                escape(host.as_deref().unwrap_or(url)),
+                link_target(url)
+            )
+        })
+        .join(" ")
+}
+
+/// Helper for percent-encoding the characters of a URL which would end the link or the table cell
+fn link_target(url: &str) -> String {
+    url.chars().fold(String::new(), |mut target, c| {
+        if c == '|' || c == '<' || c == '>' || c.is_whitespace() {
+            let mut buf = [0; 4];
+            c.encode_utf8(&mut buf)
+                .bytes()
+                .for_each(|byte| target.push_str(&format!("%{:02X}", byte)));
+        } else {
+            target.push(c);
+        }
+        target
+    })
+}
+
+/// Helper for showing a status with an emoji
+fn badge(status: Option<&Status>) -> String {
+    let emoji = match status {
+        Some(Status::Newest) => "✅",
+        Some(Status::Devel) => "🧪",
+        Some(Status::Unique) => "🔹",
+        Some(Status::Outdated) => "⚠️",
+        Some(Status::Legacy) => "🕰️",
+        Some(Status::Rolling) => "🔄",
+        Some(Status::Noscheme) => "❔",
+        Some(Status::Incorrect) => "❌",
+        Some(Status::Untrusted) => "🚫",
+        Some(Status::Ignored) => "🙈",
+        None => return String::from("No status"),
+    };
+    format!(
+        "{} {}",
+        emoji,
+        status.map(Status::to_string).unwrap_or_default()
+    )
+}
+
+/// Helper for building a table from a header and rows of already escaped cells
+fn table<S: AsRef<str>>(header: &[S], rows: impl IntoIterator<Item = Vec<String>>) -> String {
+    let header = header.iter().map(AsRef::as_ref).collect::<Vec<_>>();
+    let mut table = format!("| {} |\n", header.join(" | "));
+    table.push_str(&format!("|{}\n", " --- |".repeat(header.len())));
+    for row in rows {
+        table.push_str(&format!("| {} |\n", row.join(" | ")));
+    }
+    table
+}
+
+/// Helper for the cells of a package row: repo, name, version, status, summary, links
+fn package_cells(package: &Package) -> Vec<String> {
+    let status = if package.is_vulnerable() {
+        format!("{} 🚨 vulnerable", badge(package.status()))
+    } else {
+        badge(package.status())
+    };
+
+    vec![
+        escape(package.repo()),
+        escape(
+            package
+                .any_name()
+                .map(Name::deref)
+                .map(String::as_str)
+                .unwrap_or(""),
+        ),
+        code(package.version()),
+        status,
+        escape(package.summary().map(|s| s.deref().as_str()).unwrap_or("")),
+        links(package.www().into_iter().flatten().map(|www| www.as_str())),
+    ]
+}
+
+const PACKAGE_HEADER: &[&str] = &["Repo", "Name", "Version", "Status", "Summary", "Links"];
+
+/// Helper for the project of a package, for grouping packages by project
+fn project(package: &Package) -> &str {
+    package
+        .effname()
+        .map(|name| name.as_str())
+        .unwrap_or("<unknown>")
+}
+
+impl Frontend for MarkdownFrontend {
+    fn list_packages(
+        &self,
+        packages: Vec<Package>,
+        local_names: &LocalNames,
+        _newest: &NewestVersions,
+    ) -> Result<()> {
+        let output = packages
+            .iter()
+            .chunk_by(|package| project(package))
+            .into_iter()
+            .map(|(name, packages)| {
+                let packages = packages.collect::<Vec<_>>();
+                let heading = match packages.first() {
+                    Some(package) if crate::mapping::local_name(local_names, package) != name => {
+                        format!(
+                            "## {} (local name: {})",
+                            escape(name),
+                            escape(crate::mapping::local_name(local_names, package))
+                        )
+                    }
+                    _ => format!("## {}", escape(name)),
+                };
+
+                format!(
+                    "{}\n\n{}",
+                    heading,
+                    table(
+                        &self.package_header(),
+                        packages
+                            .into_iter()
+                            .map(|package| self.package_cells(package, local_names))
+                    )
+                )
+            })
+            .join("\n");
+
+        self.write(output)
+    }
+
+    fn list_problems(&self, problems: Vec<Problem>) -> Result<()> {
+        if let Some(columns) = self.columns.as_ref() {
+            return self.write(table(
+                &columns.titles(),
+                problems.iter().map(|problem| {
+                    columns
+                        .names()
+                        .iter()
+                        .zip(columns_cells(columns.problem(problem)))
+                        .map(|(name, cell)| match (name.as_str(), problem.version()) {
+                            ("version", Some(version)) => code(version),
+                            _ => cell,
+                        })
+                        .collect()
+                }),
+            ));
+        }
+
+        self.write(table(
+            &["Project", "Maintainer", "Problem"],
+            problems.iter().map(|problem| {
+                vec![
+                    escape(problem.project_name()),
+                    escape(problem.maintainer()),
+                    escape(problem.problem_type()),
+                ]
+            }),
+        ))
+    }
+
+    fn list_repo_diff(&self, diff: RepoDiff) -> Result<()> {
+        let repo_a = escape(diff.repo_a());
+        let repo_b = escape(diff.repo_b());
+
+        let in_both = diff.in_both().iter().map(|entry| {
+            let newer = match entry.newer() {
+                Newer::RepoA => repo_a.clone(),
+                Newer::RepoB => repo_b.clone(),
+                Newer::Same => String::from("same"),
+            };
+
+            vec![
+                escape(entry.project()),
+                code(entry.version_a()),
+                code(entry.version_b()),
+                newer,
+            ]
+        });
+        let only_in_a = diff.only_in_a().iter().map(|entry| {
+            vec![
+                escape(entry.project()),
+                code(entry.version()),
+                String::new(),
+                format!("only in {}", repo_a),
+            ]
+        });
+        let only_in_b = diff.only_in_b().iter().map(|entry| {
+            vec![
+                escape(entry.project()),
+                String::new(),
+                code(entry.version()),
+                format!("only in {}", repo_b),
+            ]
+        });
+
+        self.write(table(
+            &["Project", &repo_a, &repo_b, "Newer"],
+            in_both.chain(only_in_a).chain(only_in_b),
+        ))
+    }
+
+    fn list_relative(&self, view: RelativeView) -> Result<()> {
+        let mut header = self.package_header();
+        header.extend([String::from("Relation"), String::from("Reference version")]);
+
+        let output = view
+            .packages()
+            .iter()
+            .chunk_by(|relpkg| project(relpkg.package()))
+            .into_iter()
+            .map(|(name, relpkgs)| {
+                let rows = relpkgs.map(|relpkg| {
+                    let mut cells = self.package_cells(relpkg.package(), &LocalNames::new());
+                    cells.push(relpkg.relation().to_string());
+                    cells.push(code(relpkg.reference_version()));
+                    cells
+                });
+
+                format!(
+                    "## {} (relative to {})\n\n{}",
+                    escape(name),
+                    escape(view.reference_repo()),
+                    table(&header, rows)
+                )
+            })
+            .join("\n");
+
+        self.write(output)
+    }
+
+    fn list_report(&self, report: Report) -> Result<()> {
+        let rows = report.entries().iter().flat_map(|entry| {
+            let newest = entry
+                .newest()
+                .map(|v| code(v))
+                .unwrap_or_else(|| String::from("?"));
+
+            let pinned = entry.pinned().map(|pinned| {
+                vec![
+                    escape(&entry.display_name()),
+                    String::from("(pinned)"),
+                    code(pinned.version()),
+                    pinned
+                        .relation()
+                        .map(|r| r.to_string())
+                        .unwrap_or_else(|| String::from("unknown")),
+                    newest.clone(),
+                ]
+            });
+
+            let repositories = entry.repositories().iter().map(move |state| {
+                let version = state
+                    .version()
+                    .map(|v| code(v))
+                    .unwrap_or_else(|| String::from("-"));
+                let status = match state.version() {
+                    None => String::from("not packaged"),
+                    Some(_) if state.is_vulnerable() => {
+                        format!("{} 🚨 vulnerable", badge(state.status()))
+                    }
+                    Some(_) => badge(state.status()),
+                };
+
+                vec![
+                    escape(&entry.display_name()),
+                    escape(state.repo()),
+                    version,
+                    status,
+                    newest.clone(),
+                ]
+            });
+
+            pinned.into_iter().chain(repositories)
+        });
+
+        self.write(table(
+            &["Project", "Repo", "Version", "Freshness", "Newest"],
+            rows,
+        ))
+    }
+
+    fn list_changes(&self, changes: Vec<Change>) -> Result<()> {
+        self.write(table(
+            &["Project", "Repo", "Change"],
+            changes.iter().map(|change| {
+                vec![
+                    escape(&change.display_name()),
+                    escape(change.repo()),
+                    escape(&change.description()),
+                ]
+            }),
+        ))
+    }
+
+    fn list_violations(&self, result: PolicyResult) -> Result<()> {
+        let violations = table(
+            &["Rule", "Project", "Repo", "Violation"],
+            result.violations().iter().map(|violation| {
+                vec![
+                    escape(violation.rule()),
+                    escape(&violation.display_name()),
+                    escape(violation.repo().map(|r| r.as_str()).unwrap_or("-")),
+                    escape(violation.message()),
+                ]
+            }),
+        );
+
+        self.write(format!("{}\n`{}`\n", violations, result.summary()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::code;
+    use super::escape;
+    use super::links;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a | b"), "a \\| b");
+        assert_eq!(escape("`rm -rf`"), "\\`rm -rf\\`");
+        assert_eq!(escape("<b>*bold*</b>"), "\\<b\\>\\*bold\\*\\</b\\>");
+        assert_eq!(escape("plain text"), "plain text");
+    }
+
+    #[test]
+    fn test_escape_multi_line() {
+        assert_eq!(
+            escape("A library\nfor | tables\r\n\n  and more  "),
+            "A library for \\| tables and more"
+        );
+    }
+
+    #[test]
+    fn test_code() {
+        assert_eq!(code("1.0"), "`1.0`");
+        assert_eq!(code("1.0|2`x"), "`1.0\\|2'x`");
+    }
+
+    #[test]
+    fn test_links() {
+        assert_eq!(
+            links(vec!["https://example.org/a", "https://www.example.com/"]),
+            "[example.org](<https://example.org/a>) [www.example.com](<https://www.example.com/>)"
+        );
+        assert_eq!(
+            links(vec!["https://example.org/a|b<c>d e\tf"]),
+            "[example.org](<https://example.org/a%7Cb%3Cc%3Ed%20e%09f>)"
+        );
+        assert_eq!(links(vec!["not a url"]), "[not a url](<not%20a%20url>)");
+    }
+}
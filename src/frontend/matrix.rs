@@ -0,0 +1,239 @@
+use std::collections::BTreeSet;
+use std::ops::Deref;
+
+use anyhow::Result;
+use librepology::v1::types::Package;
+use librepology::v1::types::Problem;
+use librepology::v1::types::Repo;
+use librepology::v1::types::Status;
+use librepology::v1::types::Version;
+use librepology::v1::versioncmp;
+use prettytable::Cell;
+use prettytable::Row;
+
+use crate::diff::RepoDiff;
+use crate::frontend::color::Style;
+use crate::frontend::color::Theme;
+use crate::frontend::table::Rows;
+use crate::frontend::table::TableFrontend;
+use crate::frontend::with_vulnerable_marker;
+use crate::frontend::Frontend;
+use crate::mapping::LocalNames;
+use crate::policy::PolicyResult;
+use crate::relative::RelativeView;
+use crate::report::NewestVersions;
+use crate::report::Report;
+use crate::watch::Change;
+
+/// A Frontend that prints packages as a matrix of projects and repositories
+///
+/// There is one row per project and one column per repository, each cell holds the version of the
+/// project in the repository (the highest one, if the repository has more than one package of the
+/// project), colored by its status. The last column holds the newest known version of the project,
+/// of all its packages before filtering.
+/// The report is printed the same way.
+///
+/// Everything else is printed by the table frontend.
+pub struct MatrixFrontend {
+    table: TableFrontend,
+
+    /// The colors for the version cells, if the output is colored
+    theme: Option<Theme>,
+}
+
+impl MatrixFrontend {
+    pub fn new(table: TableFrontend, theme: Option<Theme>) -> Self {
+        MatrixFrontend { table, theme }
+    }
+
+    /// Helper for building the cell of a version, `None` if the project is not packaged
+    fn version_cell(
+        &self,
+        version: Option<&Version>,
+        status: Option<&Status>,
+        vulnerable: bool,
+    ) -> (Cell, Option<Style>) {
+        match version {
+            None => (Cell::new("-"), None),
+            Some(version) => {
+                let text = with_vulnerable_marker(version.deref().to_string(), vulnerable);
+                let style = self
+                    .theme
+                    .as_ref()
+                    .map(|theme| theme.style(status, vulnerable));
+                (Cell::new(&text), style)
+            }
+        }
+    }
+}
+
+/// Helper for building the title row, with a column for each of `repos`
+fn titles<'a, I>(first: &[&str], repos: I, last: &[&str]) -> Row
+where
+    I: IntoIterator<Item = &'a Repo>,
+{
+    let first = first.iter().copied().map(Cell::new);
+    let repos = repos.into_iter().map(|repo| Cell::new(repo.deref()));
+    let last = last.iter().copied().map(Cell::new);
+    Row::new(first.chain(repos).chain(last).collect())
+}
+
+impl Frontend for MatrixFrontend {
+    fn list_packages(
+        &self,
+        packages: Vec<Package>,
+        local_names: &LocalNames,
+        newest: &NewestVersions,
+    ) -> Result<()> {
+        let repos = packages
+            .iter()
+            .map(|package| package.repo().clone())
+            .collect::<BTreeSet<_>>();
+
+        // The projects, in the order in which they were queried
+        let mut projects: Vec<(String, Vec<Package>)> = Vec::new();
+        packages.into_iter().for_each(|package| {
+            let project = package
+                .effname()
+                .map(|name| name.deref().clone())
+                .unwrap_or_else(|| String::from("<unknown>"));
+            match projects.iter_mut().find(|(name, _)| *name == project) {
+                Some((_, packages)) => packages.push(package),
+                None => projects.push((project, vec![package])),
+            }
+        });
+
+        let first: &[&str] = if local_names.is_empty() {
+            &["Project"]
+        } else {
+            &["Local name", "Project"]
+        };
+        let titles = titles(first, repos.iter(), &["Newest"]);
+
+        let rows: Rows = projects
+            .iter()
+            .map(|(project, project_packages)| {
+                let mut cells = Vec::new();
+                let mut styles = Vec::new();
+                if !local_names.is_empty() {
+                    let local = crate::mapping::local_name(local_names, &project_packages[0]);
+                    cells.push(Cell::new(local));
+                    styles.push(None);
+                }
+                cells.push(Cell::new(project));
+                styles.push(None);
+
+                repos.iter().for_each(|repo| {
+                    let package = project_packages
+                        .iter()
+                        .filter(|package| package.repo() == repo)
+                        .max_by(|a, b| versioncmp::compare(a.version(), b.version()));
+
+                    let (cell, style) = self.version_cell(
+                        package.map(|package| package.version()),
+                        package.and_then(|package| package.status()),
+                        package
+                            .map(|package| package.is_vulnerable())
+                            .unwrap_or(false),
+                    );
+                    cells.push(cell);
+                    styles.push(style);
+                });
+
+                let newest = newest
+                    .get(project)
+                    .map(|v| v.deref().to_string())
+                    .unwrap_or_else(|| String::from("?"));
+                cells.push(Cell::new(&newest));
+
+                (Row::new(cells), styles)
+            })
+            .collect();
+
+        self.table.print(titles, rows)
+    }
+
+    fn list_problems(&self, problems: Vec<Problem>) -> Result<()> {
+        self.table.list_problems(problems)
+    }
+
+    fn list_repo_diff(&self, diff: RepoDiff) -> Result<()> {
+        self.table.list_repo_diff(diff)
+    }
+
+    fn list_relative(&self, view: RelativeView) -> Result<()> {
+        self.table.list_relative(view)
+    }
+
+    fn list_report(&self, report: Report) -> Result<()> {
+        let repos = report
+            .entries()
+            .iter()
+            .flat_map(|entry| entry.repositories().iter().map(|state| state.repo()))
+            .collect::<BTreeSet<_>>();
+
+        let pinned = report
+            .entries()
+            .iter()
+            .any(|entry| entry.pinned().is_some());
+        let last: &[&str] = if pinned {
+            &["Pinned", "Newest"]
+        } else {
+            &["Newest"]
+        };
+        let titles = titles(&["Project"], repos.iter().copied(), last);
+
+        let rows: Rows = report
+            .entries()
+            .iter()
+            .map(|entry| {
+                let mut cells = vec![Cell::new(&entry.display_name())];
+                let mut styles = vec![None];
+
+                repos.iter().for_each(|repo| {
+                    let state = entry
+                        .repositories()
+                        .iter()
+                        .find(|state| state.repo() == *repo);
+
+                    let (cell, style) = match state {
+                        Some(state) => self.version_cell(
+                            state.version(),
+                            state.status(),
+                            state.is_vulnerable(),
+                        ),
+
+                        // The repository is not watched for this project
+                        None => (Cell::new(""), None),
+                    };
+                    cells.push(cell);
+                    styles.push(style);
+                });
+
+                if pinned {
+                    let version = entry
+                        .pinned()
+                        .map(|pinned| pinned.version().deref().to_string())
+                        .unwrap_or_default();
+                    cells.push(Cell::new(&version));
+                    styles.push(None);
+                }
+
+                let newest = entry.newest().map(|v| v.deref().deref()).unwrap_or("?");
+                cells.push(Cell::new(newest));
+
+                (Row::new(cells), styles)
+            })
+            .collect();
+
+        self.table.print(titles, rows)
+    }
+
+    fn list_changes(&self, changes: Vec<Change>) -> Result<()> {
+        self.table.list_changes(changes)
+    }
+
+    fn list_violations(&self, result: PolicyResult) -> Result<()> {
+        self.table.list_violations(result)
+    }
+}
@@ -1,41 +1,359 @@
+use std::collections::BTreeMap;
+
+use anyhow::Error;
 use anyhow::Result;
 use clap::ArgMatches;
 
+use librepology::v1::envelope::Envelope;
+use librepology::v1::envelope::Query;
 use librepology::v1::types::*;
 
+use crate::backend::Backend;
 use crate::config::Configuration;
+use crate::diff::RepoDiff;
+use crate::exitcode::UsageError;
+use crate::format::Columns;
+use crate::format::LineFormat;
+use crate::frontend::color::Theme;
+#[cfg(feature = "csv_output")]
+use crate::frontend::csv::CsvFrontend;
+#[cfg(feature = "html_output")]
+use crate::frontend::html::HtmlFrontend;
 use crate::frontend::json::JsonFrontend;
 use crate::frontend::list::ListFrontend;
+use crate::frontend::markdown::MarkdownFrontend;
+use crate::frontend::matrix::MatrixFrontend;
+use crate::frontend::ndjson::NdjsonFrontend;
 use crate::frontend::table::TableFrontend;
+use crate::mapping::LocalNames;
+use crate::policy::PolicyResult;
+use crate::policy::Violation;
+use crate::relative::RelativeView;
+use crate::report::NewestVersions;
+use crate::report::Report;
+use crate::report::ReportEntry;
+use crate::watch::Change;
 
 /// A Frontend represents a way to show the data to the user
 pub trait Frontend {
-    fn list_packages(&self, packages: Vec<Package>) -> Result<()>;
+    /// List packages
+    ///
+    /// If `local_names` is not empty, the local name of each package's project is shown as well.
+    /// `newest` holds the newest known version of each project, of all its packages before
+    /// filtering.
+    fn list_packages(
+        &self,
+        packages: Vec<Package>,
+        local_names: &LocalNames,
+        newest: &NewestVersions,
+    ) -> Result<()>;
     fn list_problems(&self, problems: Vec<Problem>) -> Result<()>;
+    fn list_repo_diff(&self, diff: RepoDiff) -> Result<()>;
+    fn list_relative(&self, view: RelativeView) -> Result<()>;
+    fn list_report(&self, report: Report) -> Result<()>;
+    fn list_changes(&self, changes: Vec<Change>) -> Result<()>;
+
+    /// List the violations of a policy, followed by the summary
+    fn list_violations(&self, result: PolicyResult) -> Result<()>;
+
+    /// Whether the frontend prints results as soon as they are available
+    ///
+    /// Streaming frontends get every batch of packages, problems, report entries, changes or
+    /// policy violations passed to the `stream_*` functions as soon as it is fetched, instead of
+    /// everything at once to the `list_*` functions after all queries are done.
+    fn is_streaming(&self) -> bool {
+        false
+    }
+
+    /// Print a batch of packages, for streaming frontends
+    fn stream_packages(
+        &self,
+        packages: &[Package],
+        local_names: &LocalNames,
+        newest: &NewestVersions,
+    ) -> Result<()> {
+        self.list_packages(packages.to_vec(), local_names, newest)
+    }
+
+    /// Print a batch of problems, for streaming frontends
+    fn stream_problems(&self, problems: &[Problem]) -> Result<()> {
+        self.list_problems(problems.to_vec())
+    }
+
+    /// Print a batch of report entries, for streaming frontends
+    fn stream_report_entries(&self, entries: &[ReportEntry]) -> Result<()> {
+        self.list_report(Report::from(entries.to_vec()))
+    }
+
+    /// Print a batch of changes, for streaming frontends
+    fn stream_changes(&self, changes: &[Change]) -> Result<()> {
+        self.list_changes(changes.to_vec())
+    }
+
+    /// Print a batch of policy violations, for streaming frontends
+    fn stream_violations(&self, violations: &[Violation]) -> Result<()> {
+        self.list_violations(PolicyResult::from(violations.to_vec()))
+    }
+}
+
+/// A package together with the local name of its project, for frontends which serialize packages
+#[derive(Serialize)]
+struct MappedPackage<'a> {
+    local_name: &'a str,
+
+    #[serde(flatten)]
+    package: &'a Package,
 }
 
+impl<'a> MappedPackage<'a> {
+    fn new(package: &'a Package, local_names: &'a LocalNames) -> Self {
+        MappedPackage {
+            local_name: crate::mapping::local_name(local_names, package),
+            package,
+        }
+    }
+}
+
+pub mod color;
+#[cfg(feature = "csv_output")]
+pub mod csv;
+#[cfg(feature = "html_output")]
+pub mod html;
 pub mod json;
+pub mod layout;
 pub mod list;
+pub mod markdown;
+pub mod matrix;
+pub mod ndjson;
 pub mod table;
 
+/// Helper for highlighting the status of packages which repology marks as vulnerable
+fn with_vulnerable_marker(status: String, vulnerable: bool) -> String {
+    if vulnerable {
+        format!("{} [VULNERABLE]", status)
+    } else {
+        status
+    }
+}
+
 /// Helper function for building a new Frontend object based on the commandline parameters
-pub fn new_frontend(app: &ArgMatches, _config: &Configuration) -> Result<Box<dyn Frontend>> {
-    match app.get_one::<String>("output").map(AsRef::as_ref) {
+pub fn new_frontend(
+    app: &ArgMatches,
+    config: &Configuration,
+    backend: &Backend,
+) -> Result<Box<dyn Frontend>> {
+    let output = app.get_one::<String>("output").map(AsRef::as_ref);
+    if app.contains_id("template") && output != Some("html") {
+        return Err(Error::from(UsageError::new(
+            "--template can only be used with the html output",
+        )));
+    }
+
+    if app.contains_id("format") && output.map(|o| o != "lines").unwrap_or(false) {
+        return Err(Error::from(UsageError::new(
+            "--format can only be used with the lines output",
+        )));
+    }
+
+    let columns_outputs = ["lines", "table", "csv", "tsv", "markdown"];
+    if app.contains_id("columns")
+        && output
+            .map(|o| !columns_outputs.contains(&o))
+            .unwrap_or(false)
+    {
+        return Err(Error::from(UsageError::new(
+            "--columns can only be used with the table, lines, csv, tsv and markdown outputs",
+        )));
+    }
+
+    if app.get_flag("envelope") && output != Some("json") {
+        return Err(Error::from(UsageError::new(
+            "--envelope can only be used with the json output",
+        )));
+    }
+
+    match output {
         None | Some("lines") => {
             debug!("No output specified, using default");
-            Ok(Box::new(ListFrontend::new(::std::io::stdout())))
+            let format = line_format(app, config)?;
+            let columns = columns(app, config)?;
+            Ok(Box::new(ListFrontend::new(
+                ::std::io::stdout(),
+                format,
+                columns,
+                theme(app, config)?,
+                crate::frontend::layout::terminal_width(app),
+            )))
         }
 
         Some("json") => {
             debug!("Using JSON Frontend");
-            Ok(Box::new(JsonFrontend::new(::std::io::stdout())))
+            let envelope = if app.get_flag("envelope") {
+                Some(envelope(app, config, backend)?)
+            } else {
+                None
+            };
+            Ok(Box::new(JsonFrontend::new(::std::io::stdout(), envelope)))
+        }
+
+        #[cfg(feature = "csv_output")]
+        Some("csv") => {
+            debug!("Using CSV Frontend");
+            Ok(Box::new(CsvFrontend::new(
+                ::std::io::stdout(),
+                b',',
+                columns(app, config)?,
+            )))
+        }
+
+        #[cfg(feature = "csv_output")]
+        Some("tsv") => {
+            debug!("Using TSV Frontend");
+            Ok(Box::new(CsvFrontend::new(
+                ::std::io::stdout(),
+                b'\t',
+                columns(app, config)?,
+            )))
+        }
+
+        #[cfg(feature = "html_output")]
+        Some("html") => {
+            debug!("Using HTML Frontend");
+            let template = app.get_one::<String>("template").map(std::path::Path::new);
+            Ok(Box::new(HtmlFrontend::new(::std::io::stdout(), template)?))
+        }
+
+        Some("ndjson") => {
+            debug!("Using NDJSON Frontend");
+            Ok(Box::new(NdjsonFrontend::new(::std::io::stdout())))
+        }
+
+        Some("markdown") => {
+            debug!("Using markdown Frontend");
+            Ok(Box::new(MarkdownFrontend::new(
+                ::std::io::stdout(),
+                columns(app, config)?,
+            )))
         }
 
         Some("table") => {
             debug!("Using table Frontend");
-            Ok(Box::new(TableFrontend::new(::std::io::stdout())))
+            Ok(Box::new(table_frontend(app, config)?))
+        }
+
+        Some("matrix") => {
+            debug!("Using matrix Frontend");
+            Ok(Box::new(MatrixFrontend::new(
+                table_frontend(app, config)?,
+                theme(app, config)?,
+            )))
         }
 
         Some(other) => Err(format_err!("Unknown Frontend '{}'", other)),
     }
 }
+
+/// Helper for building the table frontend, which the matrix frontend uses as well
+fn table_frontend(app: &ArgMatches, config: &Configuration) -> Result<TableFrontend> {
+    let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for group in config.repository_groups().keys() {
+        for repo in config.resolve_repository(group) {
+            groups
+                .entry(repo.to_string())
+                .or_default()
+                .push(group.clone());
+        }
+    }
+
+    Ok(TableFrontend::new(
+        ::std::io::stdout(),
+        groups,
+        columns(app, config)?,
+        theme(app, config)?,
+        crate::frontend::layout::terminal_width(app),
+    ))
+}
+
+/// Helper for building the envelope of the json output, describing the current invocation
+///
+/// If the input is enveloped data from stdin, the fetch time and source of the input are kept, as
+/// the data was not fetched again.
+fn envelope(app: &ArgMatches, config: &Configuration, backend: &Backend) -> Result<Envelope<()>> {
+    let command = app.subcommand_name().map(String::from).unwrap_or_default();
+    let arguments = std::env::args().skip(1).collect();
+
+    let (fetched_at, source) = match backend.input_envelope()? {
+        Some(input) => (input.fetched_at().clone(), input.source().clone()),
+        None if app.get_flag("input_stdin") => {
+            (crate::time::now().to_string(), String::from("stdin"))
+        }
+        None => (
+            crate::time::now().to_string(),
+            config.repology_url().to_string(),
+        ),
+    };
+
+    Ok(Envelope::new(
+        Query::new(command, arguments),
+        fetched_at,
+        source,
+    ))
+}
+
+/// Helper for finding the line format of the current subcommand, from `--format` or the
+/// configuration
+fn line_format(app: &ArgMatches, config: &Configuration) -> Result<Option<LineFormat>> {
+    let command = app.subcommand_name().unwrap_or_default();
+    let format = app.get_one::<String>("format").or_else(|| {
+        // Columns from the commandline override the configured format
+        config
+            .formats()
+            .get(command)
+            .filter(|_| !app.contains_id("columns"))
+    });
+
+    match (format, crate::format::fields(command)) {
+        (None, _) => Ok(None),
+        (Some(format), Some(fields)) => {
+            debug!("Using line format '{}'", format);
+            LineFormat::parse(format, fields).map(Some)
+        }
+        (Some(_), None) if app.contains_id("format") => Err(Error::from(UsageError::new(
+            "--format can only be used with the project and problems subcommands",
+        ))),
+        (Some(_), None) => Ok(None),
+    }
+}
+
+/// Helper for finding the columns of the current subcommand, from `--columns` or the
+/// configuration
+fn columns(app: &ArgMatches, config: &Configuration) -> Result<Option<Columns>> {
+    let command = app.subcommand_name().unwrap_or_default();
+    let columns = match app.get_many::<String>("columns") {
+        Some(columns) => Some(columns.cloned().collect::<Vec<_>>()),
+        None => config.columns().get(command).cloned(),
+    };
+
+    match (columns, crate::format::fields(command)) {
+        (None, _) => Ok(None),
+        (Some(columns), Some(fields)) => {
+            debug!("Using columns {:?}", columns);
+            Columns::parse(&columns, fields).map(Some)
+        }
+        (Some(_), None) if app.contains_id("columns") => Err(Error::from(UsageError::new(
+            "--columns can only be used with the project and problems subcommands",
+        ))),
+        (Some(_), None) => Ok(None),
+    }
+}
+
+/// Helper for finding the color theme, if the output should be colored
+fn theme(app: &ArgMatches, config: &Configuration) -> Result<Option<Theme>> {
+    let theme = Theme::new(config.colors())?;
+    if crate::frontend::color::enabled(app) {
+        debug!("Using colors");
+        Ok(Some(theme))
+    } else {
+        Ok(None)
+    }
+}
@@ -0,0 +1,118 @@
+use std::io::Stdout;
+use std::io::Write;
+
+use anyhow::Error;
+use anyhow::Result;
+use librepology::v1::types::Package;
+use librepology::v1::types::Problem;
+use serde::Serialize;
+
+use crate::diff::RepoDiff;
+use crate::frontend::Frontend;
+use crate::frontend::MappedPackage;
+use crate::mapping::LocalNames;
+use crate::policy::PolicyResult;
+use crate::policy::Violation;
+use crate::relative::RelativeView;
+use crate::report::NewestVersions;
+use crate::report::Report;
+use crate::report::ReportEntry;
+use crate::watch::Change;
+
+/// A Frontend that prints newline-delimited JSON, one compact object per line
+///
+/// Results are printed as soon as they are fetched, so that long runs can be piped
+/// into `jq` or a log shipper without waiting for the whole run. Every package, problem, report
+/// entry, change and policy violation is one line. A repository diff is printed as one object.
+pub struct NdjsonFrontend(Stdout);
+
+impl NdjsonFrontend {
+    pub fn new(stdout: Stdout) -> Self {
+        NdjsonFrontend(stdout)
+    }
+
+    fn write<T, I>(&self, objects: I) -> Result<()>
+    where
+        T: Serialize,
+        I: IntoIterator<Item = T>,
+    {
+        let mut outlock = self.0.lock();
+        for object in objects {
+            serde_json::to_writer(&mut outlock, &object)?;
+            writeln!(outlock)?;
+        }
+        outlock.flush().map_err(Error::from)
+    }
+}
+
+impl Frontend for NdjsonFrontend {
+    fn list_packages(
+        &self,
+        packages: Vec<Package>,
+        local_names: &LocalNames,
+        newest: &NewestVersions,
+    ) -> Result<()> {
+        self.stream_packages(&packages, local_names, newest)
+    }
+
+    fn list_problems(&self, problems: Vec<Problem>) -> Result<()> {
+        self.stream_problems(&problems)
+    }
+
+    fn list_repo_diff(&self, diff: RepoDiff) -> Result<()> {
+        self.write(std::iter::once(&diff))
+    }
+
+    fn list_relative(&self, view: RelativeView) -> Result<()> {
+        self.write(view.packages())
+    }
+
+    fn list_report(&self, report: Report) -> Result<()> {
+        self.stream_report_entries(report.entries())
+    }
+
+    fn list_changes(&self, changes: Vec<Change>) -> Result<()> {
+        self.stream_changes(&changes)
+    }
+
+    fn list_violations(&self, result: PolicyResult) -> Result<()> {
+        self.stream_violations(result.violations())
+    }
+
+    fn is_streaming(&self) -> bool {
+        true
+    }
+
+    fn stream_packages(
+        &self,
+        packages: &[Package],
+        local_names: &LocalNames,
+        _newest: &NewestVersions,
+    ) -> Result<()> {
+        if local_names.is_empty() {
+            self.write(packages)
+        } else {
+            self.write(
+                packages
+                    .iter()
+                    .map(|package| MappedPackage::new(package, local_names)),
+            )
+        }
+    }
+
+    fn stream_problems(&self, problems: &[Problem]) -> Result<()> {
+        self.write(problems)
+    }
+
+    fn stream_report_entries(&self, entries: &[ReportEntry]) -> Result<()> {
+        self.write(entries)
+    }
+
+    fn stream_changes(&self, changes: &[Change]) -> Result<()> {
+        self.write(changes)
+    }
+
+    fn stream_violations(&self, violations: &[Violation]) -> Result<()> {
+        self.write(violations)
+    }
+}
@@ -1,21 +1,111 @@
+use std::collections::BTreeMap;
 use std::io::Stdout;
+use std::io::Write;
 use std::ops::Deref;
 
+use anyhow::Error;
 use anyhow::Result;
+use librepology::v1::types::EffName;
 use librepology::v1::types::Name;
 use librepology::v1::types::Package;
 use librepology::v1::types::Problem;
+use librepology::v1::types::Status;
 use prettytable::format;
+use prettytable::Cell;
+use prettytable::Row;
 use prettytable::Table;
 
+use crate::diff::Newer;
+use crate::diff::RepoDiff;
+use crate::format::Columns;
+use crate::frontend::color::Style;
+use crate::frontend::color::Theme;
+use crate::frontend::layout;
+use crate::frontend::with_vulnerable_marker;
 use crate::frontend::Frontend;
+use crate::mapping::LocalNames;
+use crate::policy::PolicyResult;
+use crate::relative::RelativeView;
+use crate::report::NewestVersions;
+use crate::report::Report;
+use crate::watch::Change;
+
+/// The rows of a table, with the style of each cell if the output is colored
+pub type Rows = Vec<(Row, Vec<Option<Style>>)>;
 
 /// A Frontend that formats the output in a nice ASCII-art table
-pub struct TableFrontend(Stdout);
+///
+/// If a terminal width is given, URLs and other long values are wrapped so that the table fits
+/// into the terminal.
+pub struct TableFrontend {
+    stdout: Stdout,
+
+    /// The names of the repository groups, by repository
+    ///
+    /// If not empty, package tables get a "Group" column.
+    groups: BTreeMap<String, Vec<String>>,
+
+    /// The columns of package and problem tables, if not the default ones
+    columns: Option<Columns>,
+
+    /// The colors for package rows, if the output is colored
+    theme: Option<Theme>,
+
+    /// The width of the terminal, if the table should fit into it
+    width: Option<usize>,
+}
 
 impl TableFrontend {
-    pub fn new(stdout: Stdout) -> Self {
-        TableFrontend(stdout)
+    pub fn new(
+        stdout: Stdout,
+        groups: BTreeMap<String, Vec<String>>,
+        columns: Option<Columns>,
+        theme: Option<Theme>,
+        width: Option<usize>,
+    ) -> Self {
+        TableFrontend {
+            stdout,
+            groups,
+            columns,
+            theme,
+            width,
+        }
+    }
+
+    /// Helper for coloring a row by the status of its package, if the output is colored
+    fn paint(
+        &self,
+        row: Row,
+        status: Option<&Status>,
+        vulnerable: bool,
+    ) -> (Row, Vec<Option<Style>>) {
+        let style = self
+            .theme
+            .as_ref()
+            .map(|theme| theme.style(status, vulnerable));
+        let styles = vec![style; row.len()];
+        (row, styles)
+    }
+
+    /// Helper for adding the "Group" title after the "Repo" title of a package table
+    fn with_group_title(&self, mut titles: Row) -> Row {
+        if !self.groups.is_empty() {
+            titles.insert_cell(4, Cell::new("Group"));
+        }
+        titles
+    }
+
+    /// Helper for adding the groups of the repository of `package` after its "Repo" cell
+    fn with_group_cell(&self, package: &Package, mut row: Row) -> Row {
+        if !self.groups.is_empty() {
+            let groups = self
+                .groups
+                .get(package.repo().deref())
+                .map(|groups| groups.join(", "))
+                .unwrap_or_default();
+            row.insert_cell(4, Cell::new(&groups));
+        }
+        row
     }
 
     fn mktable(&self) -> Table {
@@ -34,59 +124,459 @@ impl TableFrontend {
             .padding(1, 1)
             .build();
         table.set_format(format);
-        table.set_titles(row!["Name", "Version", "Repo", "Status", "URL"]);
         table
     }
 
-    fn print(&self, table: Table) -> Result<()> {
-        let mut outlock = self.0.lock();
-        table.print(&mut outlock)?;
-        Ok(())
+    /// Print a table with `titles` and `rows`
+    ///
+    /// If the table should fit into the terminal, the low-priority columns are wrapped first, then
+    /// the widest of the other columns (and titles) until the table fits. The table is rendered
+    /// without styles, so that prettytable computes the column widths from the text alone, and the
+    /// styled cells are painted in the rendered lines with `Style::paint`. Rows may have fewer
+    /// styles than cells, the remaining cells are not styled.
+    pub fn print(&self, titles: Row, rows: Rows) -> Result<()> {
+        let mut table = self.mktable();
+        let (titles, rows) = match self.width {
+            Some(available) => fit(titles, rows, available),
+            None => (titles, rows),
+        };
+
+        let title_height = height(&titles);
+        table.set_titles(titles);
+        let styled = rows
+            .into_iter()
+            .map(|(row, styles)| {
+                let cells = row
+                    .iter()
+                    .map(Cell::get_content)
+                    .zip(styles)
+                    .collect::<Vec<_>>();
+                let row_height = height(&row);
+                table.add_row(row);
+                (cells, row_height)
+            })
+            .collect::<Vec<_>>();
+
+        let mut rendered = Vec::new();
+        table.print(&mut rendered)?;
+        let rendered = String::from_utf8(rendered)?;
+
+        // The top border, the titles and their separator, then the lines of each row
+        let mut lines = rendered.lines();
+        let mut output = lines
+            .by_ref()
+            .take(title_height + 2)
+            .map(|line| format!("{}\n", line))
+            .collect::<String>();
+        styled.iter().for_each(|(cells, row_height)| {
+            lines
+                .by_ref()
+                .take(*row_height)
+                .enumerate()
+                .for_each(|(i, line)| {
+                    let cells = cells
+                        .iter()
+                        .map(|(content, style)| (content.lines().nth(i).unwrap_or(""), *style))
+                        .collect::<Vec<_>>();
+                    output.push_str(&paint_line(line, &cells));
+                    output.push('\n');
+                })
+        });
+        lines.for_each(|line| {
+            output.push_str(line);
+            output.push('\n');
+        });
+
+        let mut outlock = self.stdout.lock();
+        write!(outlock, "{}", output).map_err(Error::from)
     }
 }
 
-impl Frontend for TableFrontend {
-    fn list_packages(&self, packages: Vec<Package>) -> Result<()> {
-        let mut table = self.mktable();
-        packages.iter().for_each(|package| {
-            let status = if let Some(stat) = package.status() {
-                format!("{}", stat)
-            } else {
-                String::from("No status")
-            }; // not optimal, but works for now.
-
-            let url = if let Some(url) = package.www() {
-                if let Some(url) = url.first() {
-                    format!("{}", url.deref())
+/// Helper for the number of lines of a row, which prettytable prints with at least one line
+fn height(row: &Row) -> usize {
+    row.iter()
+        .map(|cell| cell.get_content().lines().count())
+        .max()
+        .unwrap_or(0)
+        .max(1)
+}
+
+/// Helper for painting the styled cells in a rendered line of a row
+///
+/// The line consists of the left-aligned `cells`, each after a border or column separator and a
+/// space of padding. Each cell is painted on its own, so that the borders are not colored. If the
+/// line does not look like that, it is returned unpainted.
+fn paint_line(line: &str, cells: &[(&str, Option<Style>)]) -> String {
+    let mut painted = String::new();
+    let mut rest = line;
+    for (content, style) in cells {
+        // The padding of the previous cell up to the separator, and the padding of this cell
+        let start = match rest.find('|') {
+            Some(separator) if rest[separator + 1..].starts_with(' ') => separator + 2,
+            _ => return line.to_string(),
+        };
+        if !rest[start..].starts_with(content) {
+            return line.to_string();
+        }
+
+        painted.push_str(&rest[..start]);
+        match style {
+            Some(style) if !content.is_empty() => painted.push_str(&style.paint(content)),
+            _ => painted.push_str(content),
+        }
+        rest = &rest[start + content.len()..];
+    }
+    painted.push_str(rest);
+    painted
+}
+
+/// Helper for wrapping the columns of `titles` and `rows` so that the table fits into `available`
+fn fit(titles: Row, rows: Rows, available: usize) -> (Row, Rows) {
+    let names = titles.iter().map(Cell::get_content).collect::<Vec<_>>();
+    let mut widths = names
+        .iter()
+        .map(|name| layout::width(name))
+        .collect::<Vec<_>>();
+    rows.iter().for_each(|(row, _)| {
+        row.iter().enumerate().for_each(|(i, cell)| {
+            let width = layout::width(&cell.get_content());
+            match widths.get_mut(i) {
+                Some(w) => *w = (*w).max(width),
+                None => widths.push(width),
+            }
+        })
+    });
+
+    // Borders, separators and the padding around each cell
+    let overhead = 3 * widths.len() + 1;
+    let shrinkable = layout::shrinkable(&names);
+    let mut fitted = widths.clone();
+    layout::fit(&mut fitted, &shrinkable, overhead, available);
+
+    let wrap = |row: &Row| {
+        let cells = row
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| {
+                if fitted.get(i) < widths.get(i) {
+                    Cell::new(&layout::wrap(&cell.get_content(), fitted[i]))
                 } else {
-                    String::from("")
+                    cell.clone()
                 }
-            } else {
-                String::from("")
-            }; // not optimal, but works for now
+            })
+            .collect();
+        Row::new(cells)
+    };
+
+    let rows = rows
+        .into_iter()
+        .map(|(row, styles)| (wrap(&row), styles))
+        .collect();
+    (wrap(&titles), rows)
+}
 
-            let name = package
-                .any_name()
-                .map(Name::deref)
-                .cloned()
-                .unwrap_or_else(|| String::from("<unknown>"));
+/// Helper for building the title row of selected columns
+fn columns_titles(columns: &Columns) -> Row {
+    Row::new(
+        columns
+            .titles()
+            .iter()
+            .map(|title| Cell::new(title))
+            .collect(),
+    )
+}
 
-            table.add_row(row![name, package.version(), package.repo(), status, url]);
-        });
-        self.print(table)
+/// Helper for building a table row from the values of selected columns
+fn columns_row(values: Vec<Vec<String>>) -> Row {
+    Row::new(
+        values
+            .iter()
+            .map(|values| Cell::new(&values.join(", ")))
+            .collect(),
+    )
+}
+
+/// Helper for building the table row for a package
+fn package_row(package: &Package) -> Row {
+    let status = if let Some(stat) = package.status() {
+        format!("{}", stat)
+    } else {
+        String::from("No status")
+    }; // not optimal, but works for now.
+    let status = with_vulnerable_marker(status, package.is_vulnerable());
+
+    let url = if let Some(url) = package.www() {
+        if let Some(url) = url.first() {
+            format!("{}", url.deref())
+        } else {
+            String::from("")
+        }
+    } else {
+        String::from("")
+    }; // not optimal, but works for now
+
+    let name = package
+        .any_name()
+        .map(Name::deref)
+        .cloned()
+        .unwrap_or_else(|| String::from("<unknown>"));
+
+    let project = package
+        .effname()
+        .map(EffName::deref)
+        .cloned()
+        .unwrap_or_else(|| String::from("<unknown>"));
+
+    row![
+        project,
+        name,
+        package.version(),
+        package.repo(),
+        status,
+        url
+    ]
+}
+
+impl Frontend for TableFrontend {
+    fn list_packages(
+        &self,
+        packages: Vec<Package>,
+        local_names: &LocalNames,
+        _newest: &NewestVersions,
+    ) -> Result<()> {
+        if let Some(columns) = self.columns.as_ref() {
+            let rows = packages
+                .iter()
+                .map(|package| {
+                    let row = columns_row(columns.package(package, local_names));
+                    self.paint(row, package.status(), package.is_vulnerable())
+                })
+                .collect();
+            return self.print(columns_titles(columns), rows);
+        }
+
+        let mut titles =
+            self.with_group_title(row!["Project", "Name", "Version", "Repo", "Status", "URL"]);
+        if !local_names.is_empty() {
+            titles.insert_cell(0, Cell::new("Local name"));
+        }
+
+        let rows = packages
+            .iter()
+            .map(|package| {
+                let mut row = self.with_group_cell(package, package_row(package));
+                if !local_names.is_empty() {
+                    let local = crate::mapping::local_name(local_names, package);
+                    row.insert_cell(0, Cell::new(local));
+                }
+                self.paint(row, package.status(), package.is_vulnerable())
+            })
+            .collect();
+        self.print(titles, rows)
     }
 
     fn list_problems(&self, problems: Vec<Problem>) -> Result<()> {
-        let mut table = self.mktable();
-        table.set_titles(row!["Project", "Maintainer", "Type"]);
-        problems.iter().for_each(|problem| {
-            trace!("Adding row for: {:?}", problem);
-            table.add_row(row![
-                problem.project_name(),
-                problem.maintainer(),
-                problem.problem_type()
-            ]);
+        if let Some(columns) = self.columns.as_ref() {
+            let rows = problems
+                .iter()
+                .map(|problem| (columns_row(columns.problem(problem)), Vec::new()))
+                .collect();
+            return self.print(columns_titles(columns), rows);
+        }
+
+        let rows = problems
+            .iter()
+            .map(|problem| {
+                trace!("Adding row for: {:?}", problem);
+                let row = row![
+                    problem.project_name(),
+                    problem.maintainer(),
+                    problem.problem_type()
+                ];
+                (row, Vec::new())
+            })
+            .collect();
+        self.print(row!["Project", "Maintainer", "Type"], rows)
+    }
+
+    fn list_repo_diff(&self, diff: RepoDiff) -> Result<()> {
+        let mut rows = Rows::new();
+        diff.in_both().iter().for_each(|entry| {
+            let newer = match entry.newer() {
+                Newer::RepoA => diff.repo_a().deref(),
+                Newer::RepoB => diff.repo_b().deref(),
+                Newer::Same => "same",
+            };
+
+            rows.push((
+                row![entry.project(), entry.version_a(), entry.version_b(), newer],
+                Vec::new(),
+            ));
+        });
+        diff.only_in_a().iter().for_each(|entry| {
+            let only = format!("only in {}", diff.repo_a().deref());
+            rows.push((
+                row![entry.project(), entry.version(), "-", only],
+                Vec::new(),
+            ));
+        });
+        diff.only_in_b().iter().for_each(|entry| {
+            let only = format!("only in {}", diff.repo_b().deref());
+            rows.push((
+                row![entry.project(), "-", entry.version(), only],
+                Vec::new(),
+            ));
+        });
+        self.print(row!["Project", diff.repo_a(), diff.repo_b(), "Newer"], rows)
+    }
+
+    fn list_relative(&self, view: RelativeView) -> Result<()> {
+        let reference_title = format!("{} version", view.reference_repo().deref());
+        let mut titles = match self.columns.as_ref() {
+            Some(columns) => columns_titles(columns),
+            None => {
+                self.with_group_title(row!["Project", "Name", "Version", "Repo", "Status", "URL"])
+            }
+        };
+        titles.add_cell(Cell::new(&reference_title));
+        titles.add_cell(Cell::new("Relation"));
+
+        let rows = view
+            .packages()
+            .iter()
+            .map(|relpkg| {
+                let package = relpkg.package();
+                let mut row = match self.columns.as_ref() {
+                    Some(columns) => columns_row(columns.package(package, &LocalNames::new())),
+                    None => self.with_group_cell(package, package_row(package)),
+                };
+                row.add_cell(Cell::new(relpkg.reference_version()));
+                row.add_cell(Cell::new(&relpkg.relation().to_string()));
+                self.paint(row, package.status(), package.is_vulnerable())
+            })
+            .collect();
+        self.print(titles, rows)
+    }
+
+    fn list_report(&self, report: Report) -> Result<()> {
+        let mut rows = Rows::new();
+        report.entries().iter().for_each(|entry| {
+            let newest = entry.newest().map(|v| v.deref().deref()).unwrap_or("?");
+
+            if let Some(pinned) = entry.pinned() {
+                let relation = pinned
+                    .relation()
+                    .map(|r| r.to_string())
+                    .unwrap_or_else(|| String::from("unknown"));
+
+                rows.push((
+                    row![
+                        entry.display_name(),
+                        "(pinned)",
+                        pinned.version(),
+                        relation,
+                        newest
+                    ],
+                    Vec::new(),
+                ));
+            }
+
+            entry.repositories().iter().for_each(|state| {
+                let version = state.version().map(|v| v.deref().deref()).unwrap_or("-");
+                let status = match (state.version(), state.status()) {
+                    (None, _) => String::from("not packaged"),
+                    (Some(_), Some(status)) => status.to_string(),
+                    (Some(_), None) => String::from("No status"),
+                };
+                let status = with_vulnerable_marker(status, state.is_vulnerable());
+
+                let row = row![entry.display_name(), state.repo(), version, status, newest];
+                rows.push(self.paint(row, state.status(), state.is_vulnerable()));
+            });
         });
-        self.print(table)
+        self.print(
+            row!["Project", "Repo", "Version", "Freshness", "Newest"],
+            rows,
+        )
+    }
+
+    fn list_changes(&self, changes: Vec<Change>) -> Result<()> {
+        let rows = changes
+            .iter()
+            .map(|change| {
+                let row = row![change.display_name(), change.repo(), change.description()];
+                (row, Vec::new())
+            })
+            .collect();
+        self.print(row!["Project", "Repo", "Change"], rows)
+    }
+
+    fn list_violations(&self, result: PolicyResult) -> Result<()> {
+        let rows = result
+            .violations()
+            .iter()
+            .map(|violation| {
+                let repo = violation.repo().map(|r| r.deref().deref()).unwrap_or("-");
+                let row = row![
+                    violation.rule(),
+                    violation.display_name(),
+                    repo,
+                    violation.message()
+                ];
+                (row, Vec::new())
+            })
+            .collect();
+        self.print(row!["Rule", "Project", "Repo", "Violation"], rows)?;
+
+        let mut outlock = self.stdout.lock();
+        writeln!(outlock, "summary: {}", result.summary()).map_err(Error::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use librepology::v1::types::Status;
+
+    use super::paint_line;
+    use crate::frontend::color::Theme;
+
+    #[test]
+    fn test_paint_line() {
+        let style = Theme::new(&BTreeMap::new())
+            .unwrap()
+            .style(Some(&Status::Outdated), true);
+        let paint = |text: &str| style.paint(text);
+
+        let cells = [
+            ("openssl", Some(style)),
+            ("a | b", Some(style)),
+            ("1.0", None),
+        ];
+        assert_eq!(
+            paint_line("| openssl | a | b  | 1.0 |", &cells),
+            format!("| {} | {}  | 1.0 |", paint("openssl"), paint("a | b"))
+        );
+    }
+
+    #[test]
+    fn test_paint_line_empty_cells() {
+        let style = Theme::new(&BTreeMap::new()).unwrap().style(None, true);
+
+        let cells = [("", Some(style)), ("l", Some(style))];
+        assert_eq!(
+            paint_line("|      | l    |", &cells),
+            format!("|      | {}    |", style.paint("l"))
+        );
+    }
+
+    #[test]
+    fn test_paint_line_unexpected() {
+        let style = Theme::new(&BTreeMap::new()).unwrap().style(None, true);
+
+        let cells = [("openssl", Some(style))];
+        assert_eq!(paint_line("+---------+", &cells), "+---------+");
+        assert_eq!(paint_line("| gnutls |", &cells), "| gnutls |");
     }
 }
@@ -0,0 +1,396 @@
+//! Acknowledged packages and problems
+//!
+//! Some outdated packages are pinned on purpose. The ignore file, referenced with `ignore_file`
+//! in the configuration, lists them, so that they are hidden from the output and do not trigger
+//! `--fail-on`:
+//!
+//! ```toml
+//! [[ignore]]
+//! project = "curl"
+//! repo = "debian_12"          # optional, a repository, alias or group
+//! version = "7.88.1"          # optional, a newer version is not acknowledged anymore
+//! expires = 2026-12-31        # optional, the entry is applied until (including) this date
+//! reason = "Pinned until the next stable release"
+//!
+//! [[ignore]]
+//! project = "openssl"
+//! problem = "bad license"     # entries with a problem type acknowledge problems, not packages
+//! ```
+//!
+//! Versions are compared with the repology version ordering, so "1.0" acknowledges "1.0.0" as
+//! well. Entries with a `repo` only acknowledge problems which repology lists for that repository.
+//!
+//! Expired entries are not applied and a warning is printed for each of them, so that stale pins
+//! resurface.
+
+use std::path::Path;
+
+use anyhow::Context;
+use anyhow::Error;
+use anyhow::Result;
+use librepology::v1::types::Package;
+use librepology::v1::types::Problem;
+use librepology::v1::types::Repo;
+use librepology::v1::versioncmp;
+use toml::value::Datetime;
+
+use crate::config::Configuration;
+
+#[derive(Debug, Deserialize)]
+struct IgnoreEntry {
+    #[serde(rename = "project")]
+    project: String,
+
+    #[serde(rename = "repo", default)]
+    repo: Option<String>,
+
+    #[serde(rename = "version", default)]
+    version: Option<String>,
+
+    #[serde(rename = "problem", default)]
+    problem: Option<String>,
+
+    #[serde(rename = "expires", default)]
+    expires: Option<Datetime>,
+
+    #[serde(rename = "reason", default)]
+    reason: Option<String>,
+}
+
+impl IgnoreEntry {
+    /// The expiry date as (year, month, day)
+    fn expiry_date(&self) -> Option<(u16, u8, u8)> {
+        self.expires
+            .as_ref()
+            .and_then(|expires| expires.date)
+            .map(|date| (date.year, date.month, date.day))
+    }
+
+    /// Whether the entry expired before `today`, entries are applied on their expiry date
+    fn is_expired(&self, today: (u16, u8, u8)) -> bool {
+        self.expiry_date()
+            .map(|expires| expires < today)
+            .unwrap_or(false)
+    }
+
+    /// The warning about the entry being expired
+    fn expiry_warning(&self) -> String {
+        format!(
+            "EXPIRED acknowledgement for {}: expired on {}{}",
+            self.description(),
+            self.expires
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            self.reason
+                .as_ref()
+                .map(|reason| format!(" (reason was: {})", reason))
+                .unwrap_or_default(),
+        )
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "{}{}{}{}",
+            self.project,
+            self.repo
+                .as_ref()
+                .map(|repo| format!(" in {}", repo))
+                .unwrap_or_default(),
+            self.version
+                .as_ref()
+                .map(|version| format!(" {}", version))
+                .unwrap_or_default(),
+            self.problem
+                .as_ref()
+                .map(|problem| format!(" (problem: {})", problem))
+                .unwrap_or_default(),
+        )
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct IgnoreFile {
+    #[serde(rename = "ignore", default)]
+    ignore: Vec<IgnoreEntry>,
+}
+
+/// An acknowledged entry with the repositories it applies to resolved
+#[derive(Debug)]
+struct Acknowledged {
+    entry: IgnoreEntry,
+
+    /// `None` if the entry applies to all repositories
+    repos: Option<Vec<Repo>>,
+}
+
+/// The entries of the ignore file which are not expired
+#[derive(Debug, Default)]
+pub struct IgnoreList {
+    entries: Vec<Acknowledged>,
+}
+
+impl IgnoreList {
+    /// Load the ignore file, warning about expired entries
+    pub fn load(path: &Path, config: &Configuration) -> Result<Self> {
+        let entries = IgnoreList::read(path)?;
+        Ok(IgnoreList::new(entries, config, today()))
+    }
+
+    /// Build the list from the entries which are not expired at `today`, warning about the others
+    fn new(entries: Vec<IgnoreEntry>, config: &Configuration, today: (u16, u8, u8)) -> Self {
+        let (expired, entries) = entries
+            .into_iter()
+            .partition::<Vec<_>, _>(|entry| entry.is_expired(today));
+
+        for entry in expired {
+            warn!("{}", entry.expiry_warning());
+        }
+
+        let entries = entries
+            .into_iter()
+            .map(|entry| {
+                let repos = entry
+                    .repo
+                    .as_ref()
+                    .map(|repo| config.resolve_repository(repo));
+                Acknowledged { entry, repos }
+            })
+            .collect();
+
+        IgnoreList { entries }
+    }
+
+    /// Read all entries of the ignore file, including expired ones
+    fn read(path: &Path) -> Result<Vec<IgnoreEntry>> {
+        debug!("Loading ignore file from {}", path.display());
+        let buffer = std::fs::read_to_string(path)
+            .with_context(|| format!("Reading ignore file from {}", path.display()))?;
+
+        toml::de::from_str::<IgnoreFile>(&buffer)
+            .map(|file| file.ignore)
+            .map_err(Error::from)
+            .with_context(|| format!("Parsing ignore file from {}", path.display()))
+    }
+
+    /// Descriptions of the expired entries of the ignore file at `path`
+    pub fn expired(path: &Path) -> Result<Vec<String>> {
+        let today = today();
+        Ok(IgnoreList::read(path)?
+            .into_iter()
+            .filter(|entry| entry.is_expired(today))
+            .map(|entry| entry.description())
+            .collect())
+    }
+
+    /// Whether `package` is acknowledged
+    pub fn package(&self, package: &Package) -> bool {
+        let project = package.effname().map(|name| name.as_str());
+
+        self.entries
+            .iter()
+            .filter(|ack| ack.entry.problem.is_none())
+            .filter(|ack| Some(ack.entry.project.as_str()) == project)
+            .filter(|ack| {
+                ack.repos
+                    .as_ref()
+                    .map(|repos| repos.contains(package.repo()))
+                    .unwrap_or(true)
+            })
+            .filter(|ack| {
+                ack.entry
+                    .version
+                    .as_ref()
+                    .map(|version| versioncmp::compare_str(package.version(), version).is_eq())
+                    .unwrap_or(true)
+            })
+            .inspect(|ack| trace!("Acknowledged: {:?} by {:?}", package, ack.entry))
+            .next()
+            .is_some()
+    }
+
+    /// Whether `problem` is acknowledged
+    pub fn problem(&self, problem: &Problem) -> bool {
+        self.entries
+            .iter()
+            .filter(|ack| ack.entry.project == problem.project_name().as_str())
+            .filter(|ack| ack.entry.problem.as_ref() == Some(problem.problem_type()))
+            .filter(|ack| {
+                ack.repos
+                    .as_ref()
+                    .map(|repos| problem.repo().map(|r| repos.contains(r)).unwrap_or(false))
+                    .unwrap_or(true)
+            })
+            .filter(|ack| {
+                ack.entry
+                    .version
+                    .as_ref()
+                    .map(|version| {
+                        problem
+                            .version()
+                            .map(|v| versioncmp::compare_str(v, version).is_eq())
+                            .unwrap_or(false)
+                    })
+                    .unwrap_or(true)
+            })
+            .inspect(|ack| trace!("Acknowledged: {:?} by {:?}", problem, ack.entry))
+            .next()
+            .is_some()
+    }
+}
+
+/// Today's date (UTC) as (year, month, day)
+fn today() -> (u16, u8, u8) {
+    crate::time::now()
+        .date
+        .map(|date| (date.year, date.month, date.day))
+        .unwrap_or((1970, 1, 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use librepology::v1::api::Api;
+    use librepology::v1::buffer::BufferApi;
+    use librepology::v1::types::Package;
+    use librepology::v1::types::Problem;
+
+    use super::IgnoreFile;
+    use super::IgnoreList;
+    use crate::config::Configuration;
+
+    const TODAY: (u16, u8, u8) = (2024, 2, 29);
+
+    fn list(ignore: &str, today: (u16, u8, u8)) -> IgnoreList {
+        let file: IgnoreFile = toml::de::from_str(ignore).unwrap();
+        IgnoreList::new(file.ignore, &Configuration::default(), today)
+    }
+
+    fn package(repo: &str, version: &str) -> Package {
+        serde_json::from_str(&format!(
+            r#"{{"repo": "{}", "version": "{}", "effname": "curl"}}"#,
+            repo, version
+        ))
+        .unwrap()
+    }
+
+    /// The problems of `repo`, as the backends return them
+    fn problems(repo: &str) -> Vec<Problem> {
+        let json = r#"[
+            {"project_name": "curl", "maintainer": "a@debian.org", "type": "bad license"}
+        ]"#;
+        BufferApi::read_from(json.as_bytes())
+            .unwrap()
+            .problems_for_repo(repo)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_expires_today() {
+        let ignore = r#"
+            [[ignore]]
+            project = "curl"
+            expires = 2024-02-29
+        "#;
+        assert!(list(ignore, (2024, 2, 28)).package(&package("debian_12", "7.88.1")));
+        assert!(list(ignore, TODAY).package(&package("debian_12", "7.88.1")));
+        assert!(!list(ignore, (2024, 3, 1)).package(&package("debian_12", "7.88.1")));
+    }
+
+    #[test]
+    fn test_expiry_across_boundaries() {
+        let ignore = r#"
+            [[ignore]]
+            project = "curl"
+            expires = 2023-12-31
+        "#;
+        assert!(list(ignore, (2023, 12, 31)).package(&package("debian_12", "7.88.1")));
+        assert!(!list(ignore, (2024, 1, 1)).package(&package("debian_12", "7.88.1")));
+
+        let ignore = r#"
+            [[ignore]]
+            project = "curl"
+            expires = 2024-01-31
+        "#;
+        assert!(list(ignore, (2024, 1, 31)).package(&package("debian_12", "7.88.1")));
+        assert!(!list(ignore, (2024, 2, 1)).package(&package("debian_12", "7.88.1")));
+    }
+
+    #[test]
+    fn test_expiry_warning() {
+        let file: IgnoreFile = toml::de::from_str(
+            r#"
+            [[ignore]]
+            project = "curl"
+            repo = "debian_12"
+            version = "7.88.1"
+            expires = 2024-01-31
+            reason = "Pinned until the next stable release"
+            "#,
+        )
+        .unwrap();
+        let entry = &file.ignore[0];
+
+        assert!(entry.is_expired(TODAY));
+        assert_eq!(
+            entry.expiry_warning(),
+            "EXPIRED acknowledgement for curl in debian_12 7.88.1: expired on 2024-01-31 \
+             (reason was: Pinned until the next stable release)"
+        );
+    }
+
+    #[test]
+    fn test_package_version_and_repo() {
+        let list = list(
+            r#"
+            [[ignore]]
+            project = "curl"
+            repo = "debian_12"
+            version = "7.88"
+            "#,
+            TODAY,
+        );
+        assert!(list.package(&package("debian_12", "7.88")));
+        assert!(list.package(&package("debian_12", "7.88.0")));
+        assert!(!list.package(&package("debian_12", "7.88.1")));
+        assert!(!list.package(&package("fedora_40", "7.88")));
+    }
+
+    #[test]
+    fn test_problem_repo() {
+        let list = list(
+            r#"
+            [[ignore]]
+            project = "curl"
+            repo = "debian_12"
+            problem = "bad license"
+            "#,
+            TODAY,
+        );
+        assert!(problems("debian_12").iter().all(|p| list.problem(p)));
+        assert!(!problems("fedora_40").iter().any(|p| list.problem(p)));
+    }
+
+    #[test]
+    fn test_problem_version() {
+        let list = list(
+            r#"
+            [[ignore]]
+            project = "curl"
+            problem = "bad license"
+            version = "7.88.1"
+            "#,
+            TODAY,
+        );
+        let problem = |version: &str| -> Problem {
+            serde_json::from_str(&format!(
+                r#"{{"project_name": "curl", "maintainer": "a@debian.org",
+                    "type": "bad license", "version": "{}"}}"#,
+                version
+            ))
+            .unwrap()
+        };
+        assert!(list.problem(&problem("7.88.1")));
+        assert!(!list.problem(&problem("7.88.2")));
+        assert!(!list.problem(&problems("debian_12")[0]));
+    }
+}
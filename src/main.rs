@@ -2,7 +2,7 @@ extern crate boolinator;
 extern crate filters;
 extern crate flexi_logger;
 extern crate itertools;
-extern crate semver;
+extern crate regex;
 extern crate serde;
 extern crate serde_json;
 extern crate toml;
@@ -21,9 +21,24 @@ extern crate prettytable;
 mod backend;
 mod cli;
 mod config;
+mod config_cmd;
+mod diff;
+mod exitcode;
+mod expression;
+mod filter;
+mod format;
 mod frontend;
-
-use std::cmp::Ordering;
+mod ignore;
+mod mapping;
+mod pattern;
+mod policy;
+mod relative;
+mod report;
+mod time;
+mod watch;
+
+use std::io::Read;
+use std::ops::Deref;
 use std::path::PathBuf;
 
 use anyhow::Context;
@@ -33,12 +48,22 @@ use boolinator::Boolinator;
 use clap::ArgMatches;
 use filters::filter::Filter;
 use itertools::Itertools;
-use semver::Version as SemverVersion;
 
 use config::Configuration;
+use exitcode::FailOn;
+use exitcode::UsageError;
+use expression::Expression;
+use filter::RepoFilter;
+use ignore::IgnoreList;
 use librepology::v1::api::Api;
 use librepology::v1::types::Package;
+use librepology::v1::types::Problem;
 use librepology::v1::types::Repo;
+use librepology::v1::versioncmp;
+use mapping::LocalNames;
+use mapping::NameMapping;
+use relative::RelativeView;
+use report::NewestVersions;
 
 fn initialize_logging(app: &ArgMatches) -> Result<()> {
     let verbosity = app.get_count("verbose") as usize;
@@ -68,164 +93,459 @@ fn initialize_logging(app: &ArgMatches) -> Result<()> {
         .map_err(Error::from)
 }
 
-fn app() -> Result<()> {
+/// Run repolocli, returning the exit code on success
+fn app() -> Result<i32> {
     let app = cli::build_cli().get_matches();
     initialize_logging(&app)?;
-    let config: Configuration = {
-        let path = if let Some(path) = app.get_one::<String>("config").map(PathBuf::from) {
-            debug!("Found passed configuration file at {}", path.display());
-            Ok(path)
-        } else {
-            debug!("Searching for configuration in XDG");
-            xdg::BaseDirectories::new()
-                .find_config_file("repolocli.toml")
-                .ok_or_else(|| anyhow!("Cannot find repolocli.toml"))
-        }?;
-
-        debug!("Parsing configuration from file: {}", path.display());
-
-        let buffer = std::fs::read_to_string(path).map_err(Error::from)?;
-        trace!("Config read into memory");
-        toml::de::from_str(&buffer)
-            .map_err(Error::from)
-            .context("Configuration file parsing")
-    }?;
-    trace!("Config deserialized");
+    if let Some(("config", mtch)) = app.subcommand() {
+        debug!("Subcommand: 'config'");
+        return crate::config_cmd::run(&app, mtch);
+    }
+
+    let config = Configuration::load(&app)?;
+    trace!("Config loaded");
+
+    let name_mapping = match config.name_mapping() {
+        Some(path) => NameMapping::load(path)?,
+        None => NameMapping::default(),
+    };
+    trace!("name mapping = {:?}", name_mapping);
 
     debug!("Initializing Backend");
     let backend = crate::backend::new_backend(&app, &config)?;
     debug!("Backend initialized");
 
     debug!("Initializing Frontend");
-    let frontend = crate::frontend::new_frontend(&app, &config)?;
+    let frontend = crate::frontend::new_frontend(&app, &config, &backend)?;
     debug!("Frontend initialized");
 
-    let repository_filter = {
-        let blacklist_filter = |repo: &Repo| -> bool {
-            if config.blacklist().contains(repo) {
-                trace!("In Blacklist: {:?} -> false", repo);
-                false
-            } else {
-                trace!("Not in Blacklist: {:?} -> true", repo);
-                true
-            }
-        };
+    let repository_filter = match app.subcommand().map(|(_, mtch)| mtch) {
+        Some(mtch) => RepoFilter::from_matches(mtch, &config)?,
+        None => None,
+    };
+    let repository_filter = match repository_filter {
+        Some(filter) => {
+            debug!("Using repository rules from commandline");
+            filter
+        }
+        None => RepoFilter::from_config(&config)?,
+    };
+    trace!("repository filter = {:?}", repository_filter);
+    debug!("Repository filter constructed successfully");
 
-        let whitelist_filter = |repo: &Repo| -> bool {
-            if config.whitelist().contains(repo) {
-                trace!("In Whitelist: {:?} -> true", repo);
-                true
-            } else {
-                trace!("Not in Whitelist: {:?} -> false", repo);
-                false
-            }
-        };
+    let expression = app
+        .get_one::<String>("filter")
+        .map(|e| Expression::parse(e).map(|e| e.resolve_repositories(&config)))
+        .transpose()?;
+    trace!("filter = {:?}", expression);
 
-        blacklist_filter.or(whitelist_filter)
+    let ignore_list = match config.ignore_file() {
+        Some(path) if !app.get_flag("no-ignore") => IgnoreList::load(path, &config)?,
+        _ => IgnoreList::default(),
     };
-    debug!("Repository filter constructed successfully");
+    trace!("ignore list = {:?}", ignore_list);
+
+    // The filters are only unwrapped by the subcommands which use them, as an expression may use
+    // fields which only packages or only problems have
+    let package_filter = crate::expression::filter_for::<Package>(expression.as_ref())
+        .map(|filter| filter.and(|package: &Package| !ignore_list.package(package)));
+    let problem_filter = crate::expression::filter_for::<Problem>(expression.as_ref())
+        .map(|filter| filter.and(|problem: &Problem| !ignore_list.problem(problem)));
+    debug!("Expression filters constructed successfully");
+
+    let fail_on = FailOn::from_matches(&app)?;
+    trace!("fail-on = {:?}", fail_on);
+    let mut matched_fail_on = false;
 
-    match app.subcommand() {
+    let result = match app.subcommand() {
         Some(("project", mtch)) => {
             debug!("Subcommand: 'project'");
+            let package_filter = package_filter?;
             trace!("sort-versions:   {}", mtch.get_flag("sort-version"));
             trace!("sort-repository: {}", mtch.get_flag("sort-repo"));
 
-            let name = if app.get_flag("input_stdin") {
+            let names = if app.get_flag("input_stdin") {
                 // Ugly, but works:
                 // If we have "--stdin" on CLI, we have a CLI/Stdin backend, which means that we can query
                 // _any_ "project", and get the stdin anyways. This is really not like it should be, but
                 // works for now
-                ""
+                vec![String::new()]
             } else {
-                mtch.get_one::<String>("project_name").unwrap() // safe by clap
+                project_names(mtch)?
             };
+            trace!("Projects: {:?}", names);
+
+            let reference_repos = mtch
+                .get_one::<String>("relative-to")
+                .map(|reference| (reference, config.resolve_repository(reference)));
+            trace!("Reference repositories: {:?}", reference_repos);
+
+            let mut packages = Vec::new();
+            let mut relative = Vec::new();
+            let mut local_names = LocalNames::new();
+            let mut newest = NewestVersions::new();
+            let vulnerable_only = mtch.get_flag("vulnerable-only");
+            let streaming = frontend.is_streaming();
+
+            for name in names.iter() {
+                let project = if app.get_flag("input_stdin") {
+                    name.clone()
+                } else {
+                    name_mapping.map_name(name, &mut local_names)
+                };
+
+                debug!("Fetching packages for '{}'", project);
+                let all_packages = backend.project(&project)?;
+                newest.extend(crate::report::newest_versions(&all_packages));
+
+                // The reference repository is searched before filtering, so that it is not required
+                // to be allowed by the repository rules
+                let reference = match reference_repos.as_ref() {
+                    None => None,
+                    Some((reference, repos)) => {
+                        match crate::relative::reference_version(reference, repos, &all_packages) {
+                            Ok(version) => Some((repos, version)),
+                            Err(e) if names.len() > 1 => {
+                                warn!("Skipping '{}': {}", name, e);
+                                continue;
+                            }
+                            Err(e) => return Err(e),
+                        }
+                    }
+                };
 
-            let mut packages: Vec<Package> = {
-                debug!("Fetching packages");
-                let iter = backend
-                    .project(name)?
-                    .into_iter()
-                    .filter(|package| repository_filter.filter(package.repo()));
-
-                if mtch.get_flag("sort-version") {
-                    trace!("Sorting by version");
-                    iter.sorted_by(|a, b| Ord::cmp(a.version(), b.version()))
-                        .collect()
-                } else if mtch.get_flag("sort-repo") {
-                    trace!("Sorting by repository");
-                    iter.sorted_by(|a, b| Ord::cmp(a.repo(), b.repo()))
-                        .collect()
+                let mut project_packages: Vec<Package> = {
+                    let iter = all_packages
+                        .into_iter()
+                        .filter(|package| repository_filter.filter(package.repo()))
+                        .filter(|package| package_filter.filter(package))
+                        .filter(|package| !vulnerable_only || package.is_vulnerable());
+
+                    if mtch.get_flag("sort-version") {
+                        trace!("Sorting by version");
+                        iter.sorted_by(|a, b| versioncmp::compare(a.version(), b.version()))
+                            .collect()
+                    } else if mtch.get_flag("sort-repo") {
+                        trace!("Sorting by repository");
+                        iter.sorted_by(|a, b| Ord::cmp(a.repo(), b.repo()))
+                            .collect()
+                    } else {
+                        trace!("Not sorting");
+                        iter.collect()
+                    }
+                };
+
+                if let Some((reference, reference_version)) = reference {
+                    trace!("Relating packages to {:?}", reference);
+                    relative.extend(crate::relative::relative_to(
+                        reference,
+                        &reference_version,
+                        project_packages,
+                    ));
                 } else {
-                    trace!("Not sorting");
-                    iter.collect()
+                    if mtch.get_flag("latest") {
+                        project_packages
+                            .sort_by(|a, b| versioncmp::compare(a.version(), b.version()));
+                        project_packages = project_packages.pop().into_iter().collect();
+                    }
+
+                    if streaming {
+                        frontend.stream_packages(&project_packages, &local_names, &newest)?;
+                    }
+                    packages.extend(project_packages);
                 }
-            };
+            }
 
-            let packages = if mtch.get_flag("latest") {
-                if mtch.get_flag("semver") {
-                    let comp = |a: &Package, b: &Package| {
-                        let av = SemverVersion::parse(a.version());
-                        let bv = SemverVersion::parse(b.version());
+            if let Some(reference) = mtch.get_one::<String>("relative-to") {
+                let view = RelativeView::new(Repo::new(reference.clone()), relative);
+                let view = if mtch.get_flag("ahead-only") {
+                    trace!("Removing packages which are not ahead of {}", reference);
+                    view.only_ahead()
+                } else {
+                    view
+                };
 
-                        if let (Ok(av), Ok(bv)) = (av, bv) {
-                            av.partial_cmp(&bv).unwrap_or(Ordering::Equal)
-                        } else {
-                            Ordering::Equal
-                        }
-                    };
+                matched_fail_on = view
+                    .packages()
+                    .iter()
+                    .any(|relpkg| fail_on.package(relpkg.package()));
 
-                    packages.sort_by(comp);
-                } else {
-                    packages.sort_by(|a, b| {
-                        a.version()
-                            .partial_cmp(b.version())
-                            .unwrap_or(Ordering::Equal)
-                    });
-                }
-                packages.pop().into_iter().collect::<Vec<_>>()
+                debug!("Listing relative view in frontend");
+                frontend.list_relative(view)
             } else {
-                packages
-            };
+                matched_fail_on = fail_on.packages(&packages);
 
-            debug!("Listing packages in frontend");
-            frontend.list_packages(packages)
+                if streaming {
+                    debug!("Packages were streamed to frontend");
+                    Ok(())
+                } else {
+                    debug!("Listing packages in frontend");
+                    frontend.list_packages(packages, &local_names, &newest)
+                }
+            }
         }
 
         Some(("problems", mtch)) => {
             debug!("Subcommand: 'problems'");
+            let problem_filter = problem_filter?;
 
-            let repo = mtch.get_one::<String>("repo");
+            let repo = mtch.get_one::<String>("repo").unwrap(); // safe by clap
             let maintainer = mtch.get_one::<String>("maintainer");
+            let repos = config
+                .resolve_repository(repo)
+                .into_iter()
+                .filter(|r| repository_filter.filter(r))
+                .collect::<Vec<_>>();
+            if repos.is_empty() {
+                warn!("'{}' is denied by the repository rules", repo);
+            }
 
-            trace!("repo       = {:?}", repo);
+            trace!("repo       = {} ({:?})", repo, repos);
             trace!("maintainer = {:?}", maintainer);
 
-            let problems = {
+            // Sorted problems can only be printed when all are known
+            let sort = mtch.get_flag("sort-maintainer");
+            let streaming = frontend.is_streaming() && !sort;
+
+            let problems: Vec<Problem> = {
                 debug!("Finding problems...");
-                let iter = match (repo, maintainer) {
-                    (Some(r), None) => backend.problems_for_repo(r)?,
-                    (Some(r), Some(m)) => backend.problems_for_maintainer(m, r)?,
-                    (None, _) => unreachable!("repo is required by clap"),
+                let mut all_problems = Vec::new();
+                let mut add_problems = |problems: Vec<Problem>| -> Result<()> {
+                    let problems = problems
+                        .into_iter()
+                        .filter(|problem| problem_filter.filter(problem))
+                        .collect::<Vec<_>>();
+
+                    if streaming {
+                        frontend.stream_problems(&problems)?;
+                    }
+                    all_problems.extend(problems);
+                    Ok(())
+                };
+
+                for r in repos.iter() {
+                    match maintainer {
+                        None => backend.problems_for_repo_paged(r.as_str(), &mut add_problems)?,
+                        Some(m) => add_problems(backend.problems_for_maintainer(m, r.as_str())?)?,
+                    }
                 }
-                .into_iter();
 
-                if mtch.get_flag("sort-maintainer") {
+                if sort {
                     trace!("Sorting problems by maintainer");
-                    iter.sorted_by(|a, b| Ord::cmp(a.maintainer(), b.maintainer()))
-                        .collect()
+                    all_problems.sort_by(|a, b| Ord::cmp(a.maintainer(), b.maintainer()));
                 } else {
                     trace!("Not sorting problems");
-                    iter.collect()
+                }
+                all_problems
+            };
+
+            matched_fail_on = fail_on.problems(&problems);
+
+            if streaming {
+                debug!("Problems were streamed to frontend");
+                Ok(())
+            } else {
+                debug!("Listing problems in frontend");
+                frontend.list_problems(problems)
+            }
+        }
+
+        Some(("diff-repos", mtch)) => {
+            debug!("Subcommand: 'diff-repos'");
+            let package_filter = package_filter?;
+            let repo_a = mtch.get_one::<String>("repo_a").unwrap(); // safe by clap
+            let repo_b = mtch.get_one::<String>("repo_b").unwrap(); // safe by clap
+            let repo_a = config.resolve_single_repository(repo_a)?;
+            let repo_b = config.resolve_single_repository(repo_b)?;
+            if let Some(denied) = [&repo_a, &repo_b]
+                .iter()
+                .find(|repo| !repository_filter.filter(repo))
+            {
+                return Err(Error::from(UsageError::new(format!(
+                    "Repository '{}' is denied by the repository rules, allow it with --allow-repo",
+                    denied.as_str()
+                ))));
+            }
+
+            trace!("repo_a = {}", repo_a.deref());
+            trace!("repo_b = {}", repo_b.deref());
+
+            let diff = crate::diff::diff_repos(&backend, &repo_a, &repo_b, &package_filter)?;
+            let diff = if mtch.get_flag("only-differing") {
+                trace!("Removing projects with same version");
+                diff.only_differing()
+            } else {
+                diff
+            };
+
+            debug!("Listing repository diff in frontend");
+            frontend.list_repo_diff(diff)
+        }
+
+        Some(("report", mtch)) => {
+            debug!("Subcommand: 'report'");
+            let package_filter = package_filter?;
+            if app.get_flag("input_stdin") {
+                return Err(Error::from(UsageError::new(
+                    "The 'report' subcommand cannot read data from stdin",
+                )));
+            }
+
+            if config.watchlist().is_empty() {
+                warn!("Watchlist is empty, nothing to report");
+            }
+
+            let vulnerable_only = mtch.get_flag("vulnerable-only");
+            let streaming = frontend.is_streaming();
+            let mut entries = Vec::new();
+
+            for entry in crate::report::report_entries(
+                &backend,
+                config.watchlist(),
+                &name_mapping,
+                &repository_filter,
+                &package_filter,
+            ) {
+                let entry = match entry? {
+                    entry if !vulnerable_only => entry,
+                    entry => match entry.only_vulnerable() {
+                        Some(entry) => entry,
+                        None => continue,
+                    },
+                };
+
+                if streaming {
+                    frontend.stream_report_entries(std::slice::from_ref(&entry))?;
+                }
+                entries.push(entry);
+            }
+
+            let report = crate::report::Report::from(entries);
+            matched_fail_on = fail_on.report(&report);
+
+            if streaming {
+                debug!("Report entries were streamed to frontend");
+                Ok(())
+            } else {
+                debug!("Listing report in frontend");
+                frontend.list_report(report)
+            }
+        }
+
+        Some(("watch", mtch)) => {
+            debug!("Subcommand: 'watch'");
+            let package_filter = package_filter?;
+            let problem_filter = problem_filter?;
+            if app.get_flag("input_stdin") {
+                return Err(Error::from(UsageError::new(
+                    "The 'watch' subcommand cannot read data from stdin",
+                )));
+            }
+
+            let state_path = match mtch.get_one::<String>("state") {
+                Some(path) => PathBuf::from(path),
+                None => crate::watch::default_state_path()?,
+            };
+            trace!("state = {}", state_path.display());
+
+            let interval = mtch.get_one::<u64>("interval").copied();
+            trace!("interval = {:?}", interval);
+
+            let streaming = frontend.is_streaming();
+            let run = || -> Result<()> {
+                let old = crate::watch::WatchState::load(&state_path)?;
+                let mut changes = Vec::new();
+                let new = crate::watch::fetch_state(
+                    &backend,
+                    &config,
+                    &name_mapping,
+                    &repository_filter,
+                    &package_filter,
+                    &problem_filter,
+                    |name, state| {
+                        let project_changes = old.changes(name, state);
+                        if streaming {
+                            frontend.stream_changes(&project_changes)?;
+                        }
+                        changes.extend(project_changes);
+                        Ok(())
+                    },
+                )?;
+                new.store(&state_path)?;
+
+                if streaming {
+                    debug!("{} changes were streamed to frontend", changes.len());
+                    Ok(())
+                } else {
+                    debug!("Listing {} changes in frontend", changes.len());
+                    frontend.list_changes(changes)
                 }
             };
 
-            debug!("Listing problems in frontend");
-            frontend.list_problems(problems)
+            loop {
+                let result = run();
+                match interval {
+                    None => break result,
+                    Some(secs) => {
+                        // A failed run (e.g. a network error) is retried in the next interval
+                        if let Err(e) = result {
+                            error!("Error: {}", e);
+                            e.chain()
+                                .skip(1)
+                                .for_each(|cause| error!("Caused by: {}", cause));
+                        }
+
+                        debug!("Sleeping for {} seconds", secs);
+                        std::thread::sleep(std::time::Duration::from_secs(secs));
+                    }
+                }
+            }
+        }
+
+        Some(("check", mtch)) => {
+            debug!("Subcommand: 'check'");
+            let package_filter = package_filter?;
+            let problem_filter = problem_filter?;
+            if app.get_flag("input_stdin") {
+                return Err(Error::from(UsageError::new(
+                    "The 'check' subcommand cannot read data from stdin",
+                )));
+            }
+
+            let policy_path = mtch.get_one::<String>("policy").unwrap(); // safe by clap
+            let policy = crate::policy::Policy::load(policy_path.as_ref())?;
+            let streaming = frontend.is_streaming();
+            let result = crate::policy::check_policy(
+                &backend,
+                &config,
+                &policy,
+                &name_mapping,
+                &repository_filter,
+                &package_filter,
+                &problem_filter,
+                |violations| {
+                    if streaming {
+                        frontend.stream_violations(violations)
+                    } else {
+                        Ok(())
+                    }
+                },
+            )?;
+            info!("Policy check: {}", result.summary());
+            matched_fail_on = !result.violations().is_empty();
+
+            if streaming {
+                debug!("Policy violations were streamed to frontend");
+                Ok(())
+            } else {
+                debug!("Listing policy violations in frontend");
+                frontend.list_violations(result)
+            }
         }
 
         Some((other, _mtch)) => {
             debug!("Subcommand: {}", other);
+            let package_filter = package_filter?;
             app.get_flag("input_stdin")
                 .as_result((), format_err!("Input not from stdin"))
                 .and_then(|_| {
@@ -233,21 +553,71 @@ fn app() -> Result<()> {
                     // If we have "--stdin" on CLI, we have a CLI/Stdin backend, which means that we can query
                     // _any_ "project", and get the stdin anyways. This is really not like it should be, but
                     // works for now
-                    let packages = backend
-                        .project("")?
+                    let all_packages = backend.project("")?;
+                    let newest = crate::report::newest_versions(&all_packages);
+                    let packages = all_packages
                         .into_iter()
                         .filter(|package| repository_filter.filter(package.repo()))
+                        .filter(|package| package_filter.filter(package))
                         .collect();
 
                     debug!("Listing packages");
-                    frontend.list_packages(packages)
+                    frontend.list_packages(packages, &LocalNames::new(), &newest)
                 })
-                .map_err(|_| format_err!("Unknown command: {}", other))
+                .map_err(|_| Error::from(UsageError::new(format!("Unknown command: {}", other))))
         }
         None => {
             warn!("No command. Doing nothing");
             Ok(())
         }
+    };
+
+    result.map(|_| {
+        if matched_fail_on {
+            info!("Results matched --fail-on or violated the policy");
+            exitcode::FAIL_ON
+        } else {
+            exitcode::SUCCESS
+        }
+    })
+}
+
+/// Collect the names of the projects to query for the 'project' subcommand
+///
+/// Names are taken from the commandline and from the file passed with `--from-file`. If neither is
+/// given, the names are read from stdin.
+fn project_names(mtch: &ArgMatches) -> Result<Vec<String>> {
+    fn parse_names(buffer: &str) -> impl Iterator<Item = String> + '_ {
+        buffer
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(String::from)
+    }
+
+    let mut names: Vec<String> = mtch
+        .get_many::<String>("project_name")
+        .map(|names| names.cloned().collect())
+        .unwrap_or_default();
+
+    if let Some(path) = mtch.get_one::<String>("from-file") {
+        debug!("Reading project names from {}", path);
+        let buffer = std::fs::read_to_string(path)
+            .with_context(|| format!("Reading project names from {}", path))?;
+        names.extend(parse_names(&buffer));
+    } else if names.is_empty() {
+        debug!("Reading project names from stdin");
+        let mut buffer = String::new();
+        std::io::stdin()
+            .read_to_string(&mut buffer)
+            .context("Reading project names from stdin")?;
+        names.extend(parse_names(&buffer));
+    }
+
+    if names.is_empty() {
+        Err(Error::from(UsageError::new("No project names given")))
+    } else {
+        Ok(names)
     }
 }
 
@@ -257,5 +627,11 @@ fn print_error(e: Error) {
 }
 
 fn main() {
-    let _ = app().map_err(print_error);
+    let code = app().unwrap_or_else(|e| {
+        let code = exitcode::for_error(&e);
+        print_error(e);
+        code
+    });
+
+    std::process::exit(code)
 }
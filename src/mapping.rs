@@ -0,0 +1,214 @@
+//! Mapping of local package names to repology project names
+//!
+//! Local package names (for example `python3-requests` or `libfoo-dev`) often differ from the
+//! names of the projects on repology (`python:requests`, `foo`). A mapping file, referenced with
+//! `name_mapping` in the configuration, translates them:
+//!
+//! ```toml
+//! # Exact entries, which take precedence over the rules
+//! [names]
+//! "libfoo-dev" = "foo"
+//!
+//! # Rewrite rules, the first matching rule is applied
+//! [[rules]]
+//! strip_prefix = "python3-"
+//! prefix = "python:"
+//!
+//! [[rules]]
+//! strip_prefix = "lib"
+//! strip_suffix = "-dev"
+//! ```
+//!
+//! A rule matches if the name starts with `strip_prefix` and ends with `strip_suffix` (if given).
+//! These are removed and `prefix` and `suffix` (if given) are added. Names for which there is
+//! neither an entry nor a matching rule are used as they are.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Context;
+use anyhow::Error;
+use anyhow::Result;
+use librepology::v1::types::Package;
+
+/// Local names by repology project name, for the names which were mapped to a different name
+pub type LocalNames = BTreeMap<String, String>;
+
+/// The local name of the project of `package`, which is the project name if it was not mapped
+pub fn local_name<'a>(local_names: &'a LocalNames, package: &'a Package) -> &'a str {
+    let project = package.effname().map(|n| n.as_str()).unwrap_or("<unknown>");
+    local_names
+        .get(project)
+        .map(String::as_str)
+        .unwrap_or(project)
+}
+
+/// Helper for showing the name of a project, with its local name if it was mapped
+pub fn display_name(project: &str, local_name: Option<&str>) -> String {
+    match local_name {
+        Some(local) => format!("{} (local name: {})", project, local),
+        None => project.to_string(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RewriteRule {
+    #[serde(rename = "strip_prefix", default)]
+    strip_prefix: Option<String>,
+
+    #[serde(rename = "strip_suffix", default)]
+    strip_suffix: Option<String>,
+
+    #[serde(rename = "prefix", default)]
+    prefix: Option<String>,
+
+    #[serde(rename = "suffix", default)]
+    suffix: Option<String>,
+}
+
+impl RewriteRule {
+    fn apply(&self, name: &str) -> Option<String> {
+        let name = match self.strip_prefix.as_ref() {
+            Some(prefix) => name.strip_prefix(prefix.as_str())?,
+            None => name,
+        };
+        let name = match self.strip_suffix.as_ref() {
+            Some(suffix) => name.strip_suffix(suffix.as_str())?,
+            None => name,
+        };
+
+        Some(format!(
+            "{}{}{}",
+            self.prefix.as_deref().unwrap_or(""),
+            name,
+            self.suffix.as_deref().unwrap_or("")
+        ))
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct NameMapping {
+    #[serde(rename = "names", default)]
+    names: BTreeMap<String, String>,
+
+    #[serde(rename = "rules", default)]
+    rules: Vec<RewriteRule>,
+}
+
+impl NameMapping {
+    pub fn load(path: &Path) -> Result<Self> {
+        debug!("Loading name mapping from {}", path.display());
+        let buffer = std::fs::read_to_string(path)
+            .with_context(|| format!("Reading name mapping from {}", path.display()))?;
+
+        toml::de::from_str(&buffer)
+            .map_err(Error::from)
+            .with_context(|| format!("Parsing name mapping from {}", path.display()))
+    }
+
+    /// Map a local name to the name of the repology project
+    pub fn project_name(&self, local: &str) -> String {
+        if let Some(project) = self.names.get(local) {
+            trace!("Name mapping entry: {} -> {}", local, project);
+            return project.clone();
+        }
+
+        self.rules
+            .iter()
+            .find_map(|rule| rule.apply(local))
+            .map(|project| {
+                trace!("Name mapping rule: {} -> {}", local, project);
+                project
+            })
+            .unwrap_or_else(|| local.to_string())
+    }
+
+    /// Map a local name, remembering it in `local_names` if it was mapped to a different name
+    pub fn map_name(&self, local: &str, local_names: &mut LocalNames) -> String {
+        let project = self.project_name(local);
+        if project != local {
+            debug!("Mapped local name '{}' to project '{}'", local, project);
+            local_names.insert(project.clone(), local.to_string());
+        }
+        project
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LocalNames;
+    use super::NameMapping;
+
+    fn parse(toml: &str) -> NameMapping {
+        toml::de::from_str(toml).unwrap()
+    }
+
+    const MAPPING: &str = r#"
+        [names]
+        "libfoo-dev" = "foo"
+        "python3-yaml" = "python:pyyaml"
+
+        [[rules]]
+        strip_prefix = "python3-"
+        prefix = "python:"
+
+        [[rules]]
+        strip_prefix = "lib"
+        strip_suffix = "-dev"
+
+        [[rules]]
+        strip_suffix = "-dev"
+        suffix = "-devel"
+    "#;
+
+    #[test]
+    fn test_rules() {
+        let mapping = parse(MAPPING);
+        assert_eq!(mapping.project_name("python3-requests"), "python:requests");
+        assert_eq!(mapping.project_name("libcurl-dev"), "curl");
+        assert_eq!(mapping.project_name("zlib1g-dev"), "zlib1g-devel");
+
+        // Both the prefix and the suffix of a rule have to match
+        assert_eq!(mapping.project_name("libcurl"), "libcurl");
+        assert_eq!(mapping.project_name("curl"), "curl");
+    }
+
+    #[test]
+    fn test_rule_order() {
+        // The first matching rule is applied, even if a later one matches as well
+        let mapping = parse(MAPPING);
+        assert_eq!(mapping.project_name("libssl-dev"), "ssl");
+
+        let mapping = parse(
+            r#"
+            [[rules]]
+            strip_suffix = "-dev"
+
+            [[rules]]
+            strip_prefix = "lib"
+            strip_suffix = "-dev"
+            "#,
+        );
+        assert_eq!(mapping.project_name("libssl-dev"), "libssl");
+    }
+
+    #[test]
+    fn test_names_override_rules() {
+        let mapping = parse(MAPPING);
+        assert_eq!(mapping.project_name("python3-yaml"), "python:pyyaml");
+        assert_eq!(mapping.project_name("libfoo-dev"), "foo");
+    }
+
+    #[test]
+    fn test_map_name() {
+        let mapping = parse(MAPPING);
+        let mut local_names = LocalNames::new();
+        assert_eq!(mapping.map_name("libcurl-dev", &mut local_names), "curl");
+        assert_eq!(mapping.map_name("curl", &mut local_names), "curl");
+        assert_eq!(local_names.len(), 1);
+        assert_eq!(
+            local_names.get("curl").map(String::as_str),
+            Some("libcurl-dev")
+        );
+    }
+}
@@ -0,0 +1,101 @@
+/// A pattern for matching names, for example repository names
+///
+/// Patterns are shell-like globs: `*` matches any number of characters and `?` matches exactly
+/// one character. All other characters match themselves.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Pattern(String);
+
+impl Pattern {
+    pub fn new<S: Into<String>>(pattern: S) -> Self {
+        Pattern(pattern.into())
+    }
+
+    pub fn matches(&self, s: &str) -> bool {
+        let pattern = self.0.chars().collect::<Vec<_>>();
+        let s = s.chars().collect::<Vec<_>>();
+        glob_match(&pattern, &s)
+    }
+}
+
+/// Match `s` against a glob `pattern`
+///
+/// Iterative implementation with backtracking to the last `*`, which runs in `O(n * m)`.
+fn glob_match(pattern: &[char], s: &[char]) -> bool {
+    let (mut p, mut i) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while i < s.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, i));
+                p += 1;
+            }
+            Some('?') => {
+                p += 1;
+                i += 1;
+            }
+            Some(c) if *c == s[i] => {
+                p += 1;
+                i += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    i = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pattern;
+
+    fn matches(pattern: &str, s: &str) -> bool {
+        Pattern::new(pattern).matches(s)
+    }
+
+    #[test]
+    fn test_literal() {
+        assert!(matches("debian_12", "debian_12"));
+        assert!(!matches("debian_12", "debian_11"));
+        assert!(!matches("debian", "debian_12"));
+        assert!(matches("", ""));
+        assert!(!matches("", "a"));
+    }
+
+    #[test]
+    fn test_star() {
+        assert!(matches("debian_*", "debian_12"));
+        assert!(matches("debian_*", "debian_"));
+        assert!(matches("*_12", "debian_12"));
+        assert!(matches("*", ""));
+        assert!(matches("**", "anything"));
+        assert!(!matches("debian_*", "fedora_40"));
+    }
+
+    #[test]
+    fn test_question_mark() {
+        assert!(matches("debian_1?", "debian_12"));
+        assert!(!matches("debian_1?", "debian_1"));
+        assert!(!matches("debian_?", "debian_12"));
+        assert!(matches("?*", "a"));
+        assert!(!matches("?*", ""));
+    }
+
+    #[test]
+    fn test_backtracking() {
+        assert!(matches("a*b*c", "abc"));
+        assert!(matches("a*b*c", "aXbYc"));
+        assert!(matches("a*b*c", "abbbcbc"));
+        assert!(matches("a*b*c", "acbc"));
+        assert!(!matches("a*b*c", "acb"));
+        assert!(!matches("a*b*c", "abcx"));
+        assert!(matches("*a?b", "xxa_ba_b"));
+    }
+}
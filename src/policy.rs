@@ -0,0 +1,589 @@
+//! Freshness policies, evaluated by the 'check' subcommand
+//!
+//! A policy file declares rules which the packages and problems known to repology are checked
+//! against:
+//!
+//! ```toml
+//! # Named groups of projects, which rules can refer to with "group"
+//! [project_groups]
+//! core = ["curl", "openssl"]
+//!
+//! # Our repository must not lag the newest version by more than one minor release
+//! [[rule]]
+//! name = "core-fresh"
+//! type = "max-lag"
+//! group = "core"
+//! repos = ["debian_12"]
+//! level = "minor"         # "major", "minor" or "patch"
+//! max = 1
+//!
+//! # No package may have the status "incorrect"
+//! [[rule]]
+//! name = "no-incorrect"
+//! type = "forbidden-status"
+//! status = ["incorrect"]
+//!
+//! # No open problems of the type "bad license" in debian_12
+//! [[rule]]
+//! name = "licenses"
+//! type = "no-problems"
+//! repos = ["debian_12"]
+//! problem_types = ["bad license"]   # all problem types if not given
+//! ```
+//!
+//! Every rule applies to the projects listed in `projects` and the projects of the `group`. If
+//! neither is given, it applies to the projects on the watchlist. Project names are translated
+//! with the name mapping from the configuration. `repos` lists repositories, repository aliases or
+//! groups; if it is not given, all repositories which pass the repository rules are checked.
+//! "no-problems" rules need `repos`, because repology lists problems per repository.
+//!
+//! Packages and problems which are acknowledged in the ignore file or do not pass `--filter` never
+//! violate a rule.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::ops::Deref;
+use std::path::Path;
+
+use anyhow::Context;
+use anyhow::Error;
+use anyhow::Result;
+use filters::filter::Filter;
+use librepology::v1::api::Api;
+use librepology::v1::types::Package;
+use librepology::v1::types::Problem;
+use librepology::v1::types::Repo;
+use librepology::v1::types::Status;
+use librepology::v1::types::Version;
+use librepology::v1::versioncmp;
+
+use crate::config::Configuration;
+use crate::exitcode::UsageError;
+use crate::mapping::LocalNames;
+use crate::mapping::NameMapping;
+
+/// The component of a version which the lag is measured in
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Level {
+    Major,
+    Minor,
+    Patch,
+}
+
+impl Level {
+    fn index(self) -> usize {
+        match self {
+            Level::Major => 0,
+            Level::Minor => 1,
+            Level::Patch => 2,
+        }
+    }
+}
+
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Level::Major => write!(f, "major"),
+            Level::Minor => write!(f, "minor"),
+            Level::Patch => write!(f, "patch"),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum Check {
+    MaxLag {
+        #[serde(rename = "level")]
+        level: Level,
+
+        #[serde(rename = "max", default)]
+        max: u64,
+    },
+
+    ForbiddenStatus {
+        #[serde(rename = "status")]
+        status: Vec<Status>,
+    },
+
+    NoProblems {
+        #[serde(rename = "problem_types", default)]
+        problem_types: Vec<String>,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct Rule {
+    #[serde(rename = "name")]
+    name: String,
+
+    #[serde(rename = "projects", default)]
+    projects: Vec<String>,
+
+    #[serde(rename = "group")]
+    group: Option<String>,
+
+    #[serde(rename = "repos", default)]
+    repos: Vec<String>,
+
+    #[serde(flatten)]
+    check: Check,
+}
+
+/// A policy, as read from a policy file
+#[derive(Debug, Deserialize)]
+pub struct Policy {
+    #[serde(rename = "project_groups", default)]
+    project_groups: BTreeMap<String, Vec<String>>,
+
+    #[serde(rename = "rule", default)]
+    rules: Vec<Rule>,
+}
+
+impl Policy {
+    pub fn load(path: &Path) -> Result<Self> {
+        debug!("Loading policy from {}", path.display());
+        let buffer = std::fs::read_to_string(path)
+            .with_context(|| format!("Reading policy from {}", path.display()))?;
+
+        let policy: Policy = toml::de::from_str(&buffer)
+            .map_err(Error::from)
+            .with_context(|| format!("Parsing policy from {}", path.display()))?;
+
+        policy
+            .rules
+            .iter()
+            .filter_map(|rule| rule.group.as_ref().map(|group| (rule, group)))
+            .find(|(_, group)| !policy.project_groups.contains_key(*group))
+            .map(|(rule, group)| {
+                Err(Error::from(UsageError::new(format!(
+                    "Rule '{}' refers to unknown project group '{}'",
+                    rule.name, group
+                ))))
+            })
+            .unwrap_or(Ok(()))?;
+
+        policy
+            .rules
+            .iter()
+            .find(|rule| matches!(rule.check, Check::NoProblems { .. }) && rule.repos.is_empty())
+            .map(|rule| {
+                Err(Error::from(UsageError::new(format!(
+                    "Rule '{}' checks problems, but does not list repos",
+                    rule.name
+                ))))
+            })
+            .unwrap_or(Ok(()))?;
+
+        Ok(policy)
+    }
+
+    /// The projects `rule` applies to
+    fn projects<'a>(&'a self, rule: &'a Rule, config: &'a Configuration) -> BTreeSet<&'a str> {
+        let mut projects = rule
+            .projects
+            .iter()
+            .chain(
+                rule.group
+                    .as_ref()
+                    .and_then(|group| self.project_groups.get(group))
+                    .into_iter()
+                    .flatten(),
+            )
+            .map(String::as_str)
+            .collect::<BTreeSet<_>>();
+
+        if projects.is_empty() {
+            projects.extend(config.watchlist().keys().map(String::as_str));
+        }
+        projects
+    }
+}
+
+/// A violation of a rule of the policy
+#[derive(Debug, Clone, Serialize)]
+pub struct Violation {
+    rule: String,
+    project: String,
+
+    /// The name from the policy or the watchlist, if it was mapped to a different project name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    local_name: Option<String>,
+
+    repo: Option<Repo>,
+    message: String,
+}
+
+impl Violation {
+    pub fn rule(&self) -> &str {
+        &self.rule
+    }
+
+    pub fn project(&self) -> &str {
+        &self.project
+    }
+
+    pub fn local_name(&self) -> Option<&str> {
+        self.local_name.as_deref()
+    }
+
+    /// The project name, with the local name if it was mapped
+    pub fn display_name(&self) -> String {
+        crate::mapping::display_name(&self.project, self.local_name())
+    }
+
+    pub fn repo(&self) -> Option<&Repo> {
+        self.repo.as_ref()
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Counts of rules and violations
+#[derive(Debug, Serialize)]
+pub struct Summary {
+    rules: usize,
+    passed: usize,
+    failed: usize,
+    violations: usize,
+}
+
+impl std::fmt::Display for Summary {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "rules={} passed={} failed={} violations={}",
+            self.rules, self.passed, self.failed, self.violations
+        )
+    }
+}
+
+/// The result of checking a policy
+#[derive(Debug, Serialize)]
+pub struct PolicyResult {
+    violations: Vec<Violation>,
+    summary: Summary,
+}
+
+/// The result of a batch of violations, all rules of the summary are the rules with violations
+impl From<Vec<Violation>> for PolicyResult {
+    fn from(violations: Vec<Violation>) -> Self {
+        let rules = violations
+            .iter()
+            .map(Violation::rule)
+            .collect::<BTreeSet<_>>()
+            .len();
+        let summary = Summary {
+            rules,
+            passed: 0,
+            failed: rules,
+            violations: violations.len(),
+        };
+        PolicyResult {
+            violations,
+            summary,
+        }
+    }
+}
+
+impl PolicyResult {
+    pub fn violations(&self) -> &Vec<Violation> {
+        &self.violations
+    }
+
+    pub fn summary(&self) -> &Summary {
+        &self.summary
+    }
+}
+
+/// The leading numeric components of a version, "1.2.3-r1" is [1, 2, 3]
+fn release_components(version: &Version) -> Vec<u64> {
+    version
+        .deref()
+        .split('.')
+        .map_while(|component| {
+            let digits = component
+                .chars()
+                .take_while(char::is_ascii_digit)
+                .collect::<String>();
+            digits.parse().ok()
+        })
+        .collect()
+}
+
+/// The number of `level` releases `version` is behind `newest`
+///
+/// Fails with the level of the first component which differs if `version` is behind in a
+/// component above `level` (for example a major release for `Level::Minor`), which is more than
+/// any number of releases of `level`.
+fn lag(version: &Version, newest: &Version, level: Level) -> std::result::Result<u64, Level> {
+    if versioncmp::compare(version, newest).is_ge() {
+        return Ok(0);
+    }
+
+    let ours = release_components(version);
+    let theirs = release_components(newest);
+    let component = |components: &[u64], i: usize| components.get(i).copied().unwrap_or(0);
+
+    if let Some(above) = [Level::Major, Level::Minor]
+        .iter()
+        .copied()
+        .take(level.index())
+        .find(|above| component(&ours, above.index()) != component(&theirs, above.index()))
+    {
+        return Err(above);
+    }
+
+    Ok(component(&theirs, level.index()).saturating_sub(component(&ours, level.index())))
+}
+
+/// Evaluate all rules of the `policy`
+///
+/// The violations of each rule are passed to `on_violations` as soon as the rule is evaluated.
+/// The project names are mapped with the `name_mapping` before querying. The newest version of a
+/// project is determined from all its packages, but only packages which pass the
+/// `repository_filter` (if the rule does not list repos) and the `package_filter` and problems
+/// which pass the `problem_filter` are checked.
+#[allow(clippy::too_many_arguments)]
+pub fn check_policy<A, RF, PF, QF, F>(
+    backend: &A,
+    config: &Configuration,
+    policy: &Policy,
+    name_mapping: &NameMapping,
+    repository_filter: &RF,
+    package_filter: &PF,
+    problem_filter: &QF,
+    mut on_violations: F,
+) -> Result<PolicyResult>
+where
+    A: Api,
+    RF: Filter<Repo>,
+    PF: Filter<Package>,
+    QF: Filter<Problem>,
+    F: FnMut(&[Violation]) -> Result<()>,
+{
+    let mut package_cache: BTreeMap<String, Vec<Package>> = BTreeMap::new();
+    let mut problem_cache: BTreeMap<Repo, Vec<Problem>> = BTreeMap::new();
+    let mut local_names = LocalNames::new();
+    let mut violations = Vec::new();
+    let mut failed = 0;
+
+    for rule in policy.rules.iter() {
+        debug!("Checking rule '{}'", rule.name);
+        let projects = policy
+            .projects(rule, config)
+            .into_iter()
+            .map(|name| name_mapping.map_name(name, &mut local_names))
+            .collect::<BTreeSet<_>>();
+        let repos = rule
+            .repos
+            .iter()
+            .flat_map(|repo| config.resolve_repository(repo))
+            .collect::<Vec<_>>();
+        let in_scope = |repo: &Repo| {
+            if repos.is_empty() {
+                repository_filter.filter(repo)
+            } else {
+                repos.contains(repo)
+            }
+        };
+
+        let mut rule_violations = Vec::new();
+        let mut violation = |project: &str, repo: Option<&Repo>, message: String| {
+            rule_violations.push(Violation {
+                rule: rule.name.clone(),
+                project: project.to_string(),
+                local_name: local_names.get(project).cloned(),
+                repo: repo.cloned(),
+                message,
+            })
+        };
+
+        match &rule.check {
+            Check::MaxLag { level, max } => {
+                for project in projects.iter() {
+                    let packages = fetch_packages(backend, &mut package_cache, project)?;
+                    let newest = match crate::report::newest_version(packages) {
+                        Some(newest) => newest,
+                        None => {
+                            debug!("No packages for '{}', skipping", project);
+                            continue;
+                        }
+                    };
+
+                    packages
+                        .iter()
+                        .filter(|package| in_scope(package.repo()))
+                        .filter(|package| package_filter.filter(package))
+                        .for_each(|package| match lag(package.version(), newest, *level) {
+                            Ok(lag) if lag <= *max => {}
+                            lag => violation(
+                                project,
+                                Some(package.repo()),
+                                format!(
+                                    "{} lags newest {} by {}, at most {} {} releases allowed",
+                                    package.version().deref(),
+                                    newest.deref(),
+                                    match lag {
+                                        Ok(lag) => format!("{} {} releases", lag, level),
+                                        Err(above) => format!("a {} release", above),
+                                    },
+                                    max,
+                                    level
+                                ),
+                            ),
+                        });
+                }
+            }
+
+            Check::ForbiddenStatus { status } => {
+                for project in projects.iter() {
+                    fetch_packages(backend, &mut package_cache, project)?
+                        .iter()
+                        .filter(|package| in_scope(package.repo()))
+                        .filter(|package| package_filter.filter(package))
+                        .filter_map(|package| {
+                            package
+                                .status()
+                                .filter(|s| status.contains(s))
+                                .map(|s| (package, s))
+                        })
+                        .for_each(|(package, s)| {
+                            violation(
+                                project,
+                                Some(package.repo()),
+                                format!("{} has status {}", package.version().deref(), s),
+                            )
+                        });
+                }
+            }
+
+            Check::NoProblems { problem_types } => {
+                for repo in repos.iter() {
+                    if !problem_cache.contains_key(repo) {
+                        debug!("Fetching problems for '{}'", repo.deref());
+                        let problems = backend.problems_for_repo(repo.deref())?;
+                        problem_cache.insert(repo.clone(), problems);
+                    }
+
+                    problem_cache[repo]
+                        .iter()
+                        .filter(|problem| projects.contains(problem.project_name().deref()))
+                        .filter(|problem| {
+                            problem_types.is_empty()
+                                || problem_types.contains(problem.problem_type())
+                        })
+                        .filter(|problem| problem_filter.filter(problem))
+                        .for_each(|problem| {
+                            violation(
+                                problem.project_name().deref(),
+                                Some(repo),
+                                format!("open problem: {}", problem.problem_type()),
+                            )
+                        });
+                }
+            }
+        }
+
+        if !rule_violations.is_empty() {
+            failed += 1;
+            on_violations(&rule_violations)?;
+        }
+        violations.extend(rule_violations);
+    }
+
+    let summary = Summary {
+        rules: policy.rules.len(),
+        passed: policy.rules.len() - failed,
+        failed,
+        violations: violations.len(),
+    };
+    Ok(PolicyResult {
+        violations,
+        summary,
+    })
+}
+
+/// Fetch the packages of `project`, at most once per run
+fn fetch_packages<'a, A: Api>(
+    backend: &A,
+    cache: &'a mut BTreeMap<String, Vec<Package>>,
+    project: &str,
+) -> Result<&'a Vec<Package>> {
+    if !cache.contains_key(project) {
+        debug!("Fetching packages for '{}'", project);
+        cache.insert(project.to_string(), backend.project(project)?);
+    }
+    Ok(&cache[project])
+}
+
+#[cfg(test)]
+mod tests {
+    use librepology::v1::types::Version;
+
+    use super::lag;
+    use super::release_components;
+    use super::Level;
+
+    fn version(v: &str) -> Version {
+        Version::new(v.to_string())
+    }
+
+    fn lag_of(ours: &str, newest: &str, level: Level) -> Result<u64, Level> {
+        lag(&version(ours), &version(newest), level)
+    }
+
+    #[test]
+    fn test_release_components() {
+        assert_eq!(release_components(&version("1.2.3")), vec![1, 2, 3]);
+        assert_eq!(release_components(&version("1.2.3-r1")), vec![1, 2, 3]);
+        assert_eq!(release_components(&version("2.0rc1")), vec![2, 0]);
+        assert_eq!(release_components(&version("1.x.3")), vec![1]);
+        assert!(release_components(&version("git")).is_empty());
+    }
+
+    #[test]
+    fn test_lag_per_level() {
+        assert_eq!(lag_of("1.2.3", "3.0.0", Level::Major), Ok(2));
+        assert_eq!(lag_of("1.2.3", "1.4.0", Level::Minor), Ok(2));
+        assert_eq!(lag_of("1.2.3", "1.2.5", Level::Patch), Ok(2));
+
+        // Lower components do not matter for higher levels
+        assert_eq!(lag_of("1.2.3", "1.9.9", Level::Major), Ok(0));
+        assert_eq!(lag_of("1.2.3", "1.2.9", Level::Minor), Ok(0));
+    }
+
+    #[test]
+    fn test_lag_not_behind() {
+        assert_eq!(lag_of("1.2.3", "1.2.3", Level::Patch), Ok(0));
+        assert_eq!(lag_of("1.3", "1.2.9", Level::Patch), Ok(0));
+    }
+
+    #[test]
+    fn test_lag_fewer_components() {
+        // Missing components count as 0
+        assert_eq!(lag_of("1.2", "1.2.3", Level::Patch), Ok(3));
+        assert_eq!(lag_of("1", "1.2", Level::Minor), Ok(2));
+        assert_eq!(lag_of("1.2.3", "2", Level::Major), Ok(1));
+    }
+
+    #[test]
+    fn test_lag_suffixes() {
+        assert_eq!(lag_of("1.2.3-r1", "1.2.5", Level::Patch), Ok(2));
+
+        // A pre-release of the newest release is behind it, but not by a release
+        assert_eq!(lag_of("1.2.0rc1", "1.2.0", Level::Patch), Ok(0));
+        assert_eq!(lag_of("1.1.9", "1.2.0rc1", Level::Minor), Ok(1));
+    }
+
+    #[test]
+    fn test_lag_above_level() {
+        assert_eq!(lag_of("1.2.3", "2.0.0", Level::Minor), Err(Level::Major));
+        assert_eq!(lag_of("1.2.3", "2.0.0", Level::Patch), Err(Level::Major));
+        assert_eq!(lag_of("1.2.3", "1.3.0", Level::Patch), Err(Level::Minor));
+        assert_eq!(lag_of("1.2", "2", Level::Minor), Err(Level::Major));
+    }
+}
@@ -0,0 +1,137 @@
+use std::cmp::Ordering;
+use std::ops::Deref;
+
+use anyhow::Result;
+use librepology::v1::types::Package;
+use librepology::v1::types::Repo;
+use librepology::v1::types::Version;
+use librepology::v1::versioncmp;
+
+/// How the version of a package relates to the version in the reference repository
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+pub enum Relation {
+    #[serde(rename = "older")]
+    Older,
+
+    #[serde(rename = "same")]
+    Same,
+
+    #[serde(rename = "newer")]
+    Newer,
+}
+
+impl From<Ordering> for Relation {
+    fn from(ord: Ordering) -> Self {
+        match ord {
+            Ordering::Less => Relation::Older,
+            Ordering::Equal => Relation::Same,
+            Ordering::Greater => Relation::Newer,
+        }
+    }
+}
+
+impl std::fmt::Display for Relation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Relation::Older => write!(f, "older"),
+            Relation::Same => write!(f, "same"),
+            Relation::Newer => write!(f, "newer"),
+        }
+    }
+}
+
+/// A package together with its relation to the reference repository
+#[derive(Debug, Serialize)]
+pub struct RelativePackage {
+    #[serde(flatten)]
+    package: Package,
+    reference_version: Version,
+    relation: Relation,
+}
+
+impl RelativePackage {
+    pub fn package(&self) -> &Package {
+        &self.package
+    }
+
+    pub fn reference_version(&self) -> &Version {
+        &self.reference_version
+    }
+
+    pub fn relation(&self) -> Relation {
+        self.relation
+    }
+}
+
+/// Packages of one or more projects, viewed relative to the packages in a reference repository
+#[derive(Debug, Serialize)]
+pub struct RelativeView {
+    reference_repo: Repo,
+    packages: Vec<RelativePackage>,
+}
+
+impl RelativeView {
+    pub fn new(reference_repo: Repo, packages: Vec<RelativePackage>) -> Self {
+        RelativeView {
+            reference_repo,
+            packages,
+        }
+    }
+
+    pub fn reference_repo(&self) -> &Repo {
+        &self.reference_repo
+    }
+
+    pub fn packages(&self) -> &Vec<RelativePackage> {
+        &self.packages
+    }
+
+    /// Remove all packages which are not newer than the reference
+    pub fn only_ahead(mut self) -> Self {
+        self.packages.retain(|p| p.relation == Relation::Newer);
+        self
+    }
+}
+
+/// Find the newest version of the project in the `reference` repository
+///
+/// `repos` are the repositories `reference` stands for, which are more than one if `reference` is
+/// a repository group.
+pub fn reference_version(reference: &str, repos: &[Repo], packages: &[Package]) -> Result<Version> {
+    packages
+        .iter()
+        .filter(|package| repos.contains(package.repo()))
+        .map(Package::version)
+        .max_by(|a, b| versioncmp::compare(a, b))
+        .cloned()
+        .ok_or_else(|| {
+            format_err!(
+                "Project is not packaged in reference repository '{}'",
+                reference
+            )
+        })
+}
+
+/// Relate `packages` to `reference_version` from the `reference` repositories
+///
+/// The packages of the reference repositories themselves are not part of the result.
+pub fn relative_to(
+    reference: &[Repo],
+    reference_version: &Version,
+    packages: Vec<Package>,
+) -> Vec<RelativePackage> {
+    trace!("Reference version: {}", reference_version.deref());
+    packages
+        .into_iter()
+        .filter(|package| !reference.contains(package.repo()))
+        .map(|package| {
+            let relation =
+                Relation::from(versioncmp::compare(package.version(), reference_version));
+            RelativePackage {
+                package,
+                reference_version: reference_version.clone(),
+                relation,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,309 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::ops::Deref;
+
+use anyhow::Result;
+use filters::filter::Filter;
+use itertools::Itertools;
+use librepology::v1::api::Api;
+use librepology::v1::types::Name;
+use librepology::v1::types::Package;
+use librepology::v1::types::Repo;
+use librepology::v1::types::Status;
+use librepology::v1::types::Version;
+use librepology::v1::versioncmp;
+
+use crate::config::WatchlistEntry;
+use crate::mapping::NameMapping;
+use crate::relative::Relation;
+
+/// The state of a watched project in one repository
+#[derive(Debug, Clone, Serialize)]
+pub struct RepositoryState {
+    repo: Repo,
+
+    /// The version in the repository, `None` if the project is not packaged in the repository
+    version: Option<Version>,
+    status: Option<Status>,
+
+    /// Whether repology marks any package of the project in the repository as potentially
+    /// vulnerable, not only the one with the highest version
+    vulnerable: bool,
+}
+
+impl RepositoryState {
+    pub fn repo(&self) -> &Repo {
+        &self.repo
+    }
+
+    pub fn version(&self) -> Option<&Version> {
+        self.version.as_ref()
+    }
+
+    pub fn status(&self) -> Option<&Status> {
+        self.status.as_ref()
+    }
+
+    pub fn is_vulnerable(&self) -> bool {
+        self.vulnerable
+    }
+}
+
+/// The state of the locally pinned version of a watched project
+#[derive(Debug, Clone, Serialize)]
+pub struct PinnedState {
+    version: Version,
+
+    /// Relation of the pinned version to the newest version, `None` if no newest version is known
+    relation: Option<Relation>,
+}
+
+impl PinnedState {
+    pub fn version(&self) -> &Version {
+        &self.version
+    }
+
+    pub fn relation(&self) -> Option<Relation> {
+        self.relation
+    }
+}
+
+/// The freshness of one watched project
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportEntry {
+    project: Name,
+
+    /// The name on the watchlist, if it was mapped to a different project name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    local_name: Option<String>,
+
+    newest: Option<Version>,
+    pinned: Option<PinnedState>,
+    repositories: Vec<RepositoryState>,
+}
+
+impl ReportEntry {
+    pub fn project(&self) -> &Name {
+        &self.project
+    }
+
+    pub fn local_name(&self) -> Option<&str> {
+        self.local_name.as_deref()
+    }
+
+    /// The project name, with the local name if it was mapped
+    pub fn display_name(&self) -> String {
+        crate::mapping::display_name(&self.project, self.local_name())
+    }
+
+    pub fn newest(&self) -> Option<&Version> {
+        self.newest.as_ref()
+    }
+
+    pub fn pinned(&self) -> Option<&PinnedState> {
+        self.pinned.as_ref()
+    }
+
+    pub fn repositories(&self) -> &Vec<RepositoryState> {
+        &self.repositories
+    }
+
+    /// Remove all repositories without a vulnerable package, `None` if no repository is left
+    pub fn only_vulnerable(mut self) -> Option<Self> {
+        self.repositories.retain(|state| state.vulnerable);
+        Some(self).filter(|entry| !entry.repositories.is_empty())
+    }
+}
+
+/// A freshness report over all projects on the watchlist
+#[derive(Debug, Serialize)]
+pub struct Report {
+    entries: Vec<ReportEntry>,
+}
+
+impl Report {
+    pub fn entries(&self) -> &Vec<ReportEntry> {
+        &self.entries
+    }
+}
+
+impl From<Vec<ReportEntry>> for Report {
+    fn from(entries: Vec<ReportEntry>) -> Self {
+        Report { entries }
+    }
+}
+
+/// The newest known version by project name, of all packages of the projects before filtering
+pub type NewestVersions = BTreeMap<String, Version>;
+
+/// Find the newest known version of each project of `packages`
+pub fn newest_versions(packages: &[Package]) -> NewestVersions {
+    packages
+        .iter()
+        .into_group_map_by(|package| {
+            package
+                .effname()
+                .map(|name| name.deref().clone())
+                .unwrap_or_else(|| String::from("<unknown>"))
+        })
+        .into_iter()
+        .filter_map(|(project, packages)| {
+            newest_version(packages).map(|version| (project, version.clone()))
+        })
+        .collect()
+}
+
+/// Find the newest known version of a project
+///
+/// This is the version of the packages which repology marked as "newest". If there are no such
+/// packages, the highest version of all packages is used.
+pub fn newest_version<'a, I>(packages: I) -> Option<&'a Version>
+where
+    I: IntoIterator<Item = &'a Package>,
+    I::IntoIter: Clone,
+{
+    let compare = |a: &&Version, b: &&Version| versioncmp::compare(a, b);
+    let packages = packages.into_iter();
+
+    packages
+        .clone()
+        .filter(|p| p.status() == Some(&Status::Newest))
+        .map(Package::version)
+        .max_by(compare)
+        .or_else(|| packages.map(Package::version).max_by(compare))
+}
+
+fn report_entry<RF>(
+    project: &str,
+    local_name: Option<&str>,
+    entry: &WatchlistEntry,
+    packages: &[Package],
+    repository_filter: &RF,
+) -> ReportEntry
+where
+    RF: Filter<Repo>,
+{
+    let newest = newest_version(packages).cloned();
+
+    let pinned = entry.version().map(|version| {
+        let version = Version::new(version.clone());
+        let relation = newest
+            .as_ref()
+            .map(|newest| Relation::from(versioncmp::compare(&version, newest)));
+        PinnedState { version, relation }
+    });
+
+    // Without configured repositories, the project is reported for all repositories which pass
+    // the repository rules
+    let repos = if entry.repositories().is_empty() {
+        packages
+            .iter()
+            .map(Package::repo)
+            .filter(|repo| repository_filter.filter(repo))
+            .map(|repo| repo.deref().clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    } else {
+        entry.repositories().clone()
+    };
+
+    let repositories = repos
+        .iter()
+        .map(|repo| {
+            let repo_packages = packages
+                .iter()
+                .filter(|p| p.repo().deref() == repo)
+                .collect::<Vec<_>>();
+            let package = repo_packages
+                .iter()
+                .max_by(|a, b| versioncmp::compare(a.version(), b.version()));
+
+            RepositoryState {
+                repo: Repo::new(repo.clone()),
+                version: package.map(|p| p.version()).cloned(),
+                status: package.and_then(|p| p.status()).cloned(),
+                vulnerable: repo_packages.iter().any(|p| p.is_vulnerable()),
+            }
+        })
+        .collect();
+
+    ReportEntry {
+        project: Name::new(project.to_string()),
+        local_name: local_name.map(String::from),
+        newest,
+        pinned,
+        repositories,
+    }
+}
+
+/// Query the projects of the `watchlist` one by one, yielding the report entry of each project as
+/// soon as it is fetched
+///
+/// The names on the watchlist are mapped with the `name_mapping` before querying. Only packages
+/// which pass the `package_filter` are considered. Projects without configured repositories are
+/// reported for the repositories which pass the `repository_filter`.
+pub fn report_entries<'a, A, I, RF, F>(
+    backend: &'a A,
+    watchlist: I,
+    name_mapping: &'a NameMapping,
+    repository_filter: &'a RF,
+    package_filter: &'a F,
+) -> impl Iterator<Item = Result<ReportEntry>> + 'a
+where
+    A: Api,
+    I: IntoIterator<Item = (&'a String, &'a WatchlistEntry)>,
+    I::IntoIter: 'a,
+    RF: Filter<Repo>,
+    F: Filter<Package>,
+{
+    watchlist.into_iter().map(move |(name, entry)| {
+        let project = name_mapping.project_name(name);
+        let local_name = Some(name.as_str()).filter(|name| *name != project);
+
+        debug!("Fetching packages for '{}'", project);
+        let packages = backend
+            .project(&project)?
+            .into_iter()
+            .filter(|package| package_filter.filter(package))
+            .collect::<Vec<_>>();
+        Ok(report_entry(
+            &project,
+            local_name,
+            entry,
+            &packages,
+            repository_filter,
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use librepology::v1::types::Package;
+    use librepology::v1::types::Repo;
+
+    use super::report_entry;
+    use crate::config::WatchlistEntry;
+
+    #[test]
+    fn test_vulnerable_older_package() {
+        let packages: Vec<Package> = serde_json::from_str(
+            r#"[
+                {"repo": "debian_12", "version": "2.7.18", "status": "legacy", "vulnerable": true},
+                {"repo": "debian_12", "version": "3.11.2", "status": "outdated"},
+                {"repo": "fedora_40", "version": "3.12.3", "status": "newest"}
+            ]"#,
+        )
+        .unwrap();
+        let entry: WatchlistEntry = toml::de::from_str("").unwrap();
+        let entry = report_entry("python", None, &entry, &packages, &|_: &Repo| true);
+
+        let states = entry.repositories();
+        assert_eq!(states[0].version().map(|v| v.as_str()), Some("3.11.2"));
+        assert!(states[0].is_vulnerable());
+        assert!(!states[1].is_vulnerable());
+
+        let entry = entry.only_vulnerable().unwrap();
+        assert_eq!(entry.repositories().len(), 1);
+    }
+}
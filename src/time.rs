@@ -0,0 +1,82 @@
+//! Helpers for the current point in time, without pulling in a date library
+
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use toml::value::Date;
+use toml::value::Datetime;
+use toml::value::Offset;
+use toml::value::Time;
+
+/// The current point in time (UTC), with second precision
+pub fn now() -> Datetime {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    from_unix(secs)
+}
+
+/// The point in time (UTC) `secs` seconds after the epoch
+fn from_unix(secs: u64) -> Datetime {
+    let days = (secs / 86400) as i64;
+    let secs_of_day = secs % 86400;
+
+    // Conversion from days since the epoch to the civil date, from
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    Datetime {
+        date: Some(Date {
+            year: year as u16,
+            month: month as u8,
+            day: day as u8,
+        }),
+        time: Some(Time {
+            hour: (secs_of_day / 3600) as u8,
+            minute: (secs_of_day % 3600 / 60) as u8,
+            second: Some((secs_of_day % 60) as u8),
+            nanosecond: None,
+        }),
+        offset: Some(Offset::Z),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::from_unix;
+
+    fn format(secs: u64) -> String {
+        from_unix(secs).to_string()
+    }
+
+    #[test]
+    fn test_epoch() {
+        assert_eq!(format(0), "1970-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn test_year_boundary() {
+        assert_eq!(format(1_704_067_199), "2023-12-31T23:59:59Z");
+        assert_eq!(format(1_704_067_200), "2024-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn test_leap_years() {
+        assert_eq!(format(951_827_445), "2000-02-29T12:30:45Z");
+        assert_eq!(format(1_709_164_800), "2024-02-29T00:00:00Z");
+        assert_eq!(format(1_709_251_200), "2024-03-01T00:00:00Z");
+
+        // 2100 is divisible by 100, but not by 400, so it is not a leap year
+        assert_eq!(format(4_107_456_000), "2100-02-28T00:00:00Z");
+        assert_eq!(format(4_107_542_400), "2100-03-01T00:00:00Z");
+    }
+}
@@ -0,0 +1,397 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::ops::Deref;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Error;
+use anyhow::Result;
+use filters::filter::Filter;
+use librepology::v1::api::Api;
+use librepology::v1::types::Package;
+use librepology::v1::types::Problem;
+use librepology::v1::types::Repo;
+use librepology::v1::types::Status;
+use librepology::v1::types::Version;
+use librepology::v1::versioncmp;
+
+use crate::config::Configuration;
+use crate::mapping::NameMapping;
+
+/// The last seen state of a package of a watched project in one repository
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+struct PackageState {
+    version: Version,
+    status: Option<Status>,
+}
+
+/// The last seen state of a watched project
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProjectState {
+    /// The repology project, if the name on the watchlist was mapped to a different name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+
+    /// Packages, by repository
+    packages: BTreeMap<String, PackageState>,
+
+    /// Problems as pairs of repository and problem type
+    problems: BTreeSet<(String, String)>,
+}
+
+/// The state which is persisted between two runs of the 'watch' subcommand
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WatchState {
+    /// Projects, by their name on the watchlist
+    projects: BTreeMap<String, ProjectState>,
+}
+
+impl WatchState {
+    /// Load the state from `path`, or start with an empty state if there is no such file
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            info!("No previous state found at {}", path.display());
+            return Ok(WatchState::default());
+        }
+
+        debug!("Loading state from {}", path.display());
+        let buffer = std::fs::read_to_string(path)?;
+        serde_json::from_str(&buffer)
+            .map_err(Error::from)
+            .with_context(|| format!("Parsing watch state from {}", path.display()))
+    }
+
+    pub fn store(&self, path: &Path) -> Result<()> {
+        debug!("Storing state to {}", path.display());
+        let buffer = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, buffer)
+            .with_context(|| format!("Writing watch state to {}", path.display()))
+    }
+
+    /// The changes of the project `watched` (the name on the watchlist) from this state to the
+    /// `new_state`
+    pub fn changes(&self, watched: &str, new_state: &ProjectState) -> Vec<Change> {
+        let empty = ProjectState::default();
+        let old_state = self.projects.get(watched).unwrap_or(&empty);
+        changes(watched, old_state, new_state)
+    }
+}
+
+/// The name of a watched project, with the name on the watchlist if it was mapped
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectName {
+    project: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    local_name: Option<String>,
+}
+
+/// A change of a watched project since the last run
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "change")]
+pub enum Change {
+    /// The project appeared in a repository
+    #[serde(rename = "new_package")]
+    NewPackage {
+        #[serde(flatten)]
+        name: ProjectName,
+        repo: Repo,
+        version: Version,
+    },
+
+    /// The project disappeared from a repository
+    #[serde(rename = "removed_package")]
+    RemovedPackage {
+        #[serde(flatten)]
+        name: ProjectName,
+        repo: Repo,
+        version: Version,
+    },
+
+    #[serde(rename = "new_version")]
+    NewVersion {
+        #[serde(flatten)]
+        name: ProjectName,
+        repo: Repo,
+        old: Version,
+        new: Version,
+    },
+
+    #[serde(rename = "status")]
+    StatusTransition {
+        #[serde(flatten)]
+        name: ProjectName,
+        repo: Repo,
+        old: Option<Status>,
+        new: Option<Status>,
+    },
+
+    #[serde(rename = "new_problem")]
+    NewProblem {
+        #[serde(flatten)]
+        name: ProjectName,
+        repo: Repo,
+        problem_type: String,
+    },
+
+    #[serde(rename = "resolved_problem")]
+    ResolvedProblem {
+        #[serde(flatten)]
+        name: ProjectName,
+        repo: Repo,
+        problem_type: String,
+    },
+}
+
+impl Change {
+    fn name(&self) -> &ProjectName {
+        match self {
+            Change::NewPackage { name, .. }
+            | Change::RemovedPackage { name, .. }
+            | Change::NewVersion { name, .. }
+            | Change::StatusTransition { name, .. }
+            | Change::NewProblem { name, .. }
+            | Change::ResolvedProblem { name, .. } => name,
+        }
+    }
+
+    pub fn project(&self) -> &str {
+        &self.name().project
+    }
+
+    pub fn local_name(&self) -> Option<&str> {
+        self.name().local_name.as_deref()
+    }
+
+    /// The project name, with the local name if it was mapped
+    pub fn display_name(&self) -> String {
+        crate::mapping::display_name(self.project(), self.local_name())
+    }
+
+    pub fn repo(&self) -> &Repo {
+        match self {
+            Change::NewPackage { repo, .. }
+            | Change::RemovedPackage { repo, .. }
+            | Change::NewVersion { repo, .. }
+            | Change::StatusTransition { repo, .. }
+            | Change::NewProblem { repo, .. }
+            | Change::ResolvedProblem { repo, .. } => repo,
+        }
+    }
+
+    /// A human readable description of the change
+    pub fn description(&self) -> String {
+        let status = |s: &Option<Status>| {
+            s.as_ref()
+                .map(Status::to_string)
+                .unwrap_or_else(|| String::from("No status"))
+        };
+
+        match self {
+            Change::NewPackage { version, .. } => format!("new package: {}", version.deref()),
+            Change::RemovedPackage { version, .. } => {
+                format!("removed package: {}", version.deref())
+            }
+            Change::NewVersion { old, new, .. } => {
+                format!("new version: {} -> {}", old.deref(), new.deref())
+            }
+            Change::StatusTransition { old, new, .. } => {
+                format!("status: {} -> {}", status(old), status(new))
+            }
+            Change::NewProblem { problem_type, .. } => format!("new problem: {}", problem_type),
+            Change::ResolvedProblem { problem_type, .. } => {
+                format!("resolved problem: {}", problem_type)
+            }
+        }
+    }
+}
+
+/// The default location of the state file
+pub fn default_state_path() -> Result<PathBuf> {
+    xdg::BaseDirectories::with_prefix("repolocli")
+        .place_data_file("watch-state.json")
+        .map_err(Error::from)
+        .context("Finding location for watch state")
+}
+
+/// Fetch the current state of all projects on the watchlist
+///
+/// The projects are fetched one by one, the name on the watchlist and the state of each project
+/// are passed to `on_project` as soon as it is fetched.
+///
+/// The names on the watchlist are mapped with the `name_mapping` before querying. If a project
+/// has no repositories of interest configured, all repositories which pass the
+/// `repository_filter` are watched, and problems are watched in the repositories the project was
+/// found in. Only packages which pass the `package_filter` and problems which pass the
+/// `problem_filter` are part of the state.
+pub fn fetch_state<A, RF, PF, QF, F>(
+    backend: &A,
+    config: &Configuration,
+    name_mapping: &NameMapping,
+    repository_filter: &RF,
+    package_filter: &PF,
+    problem_filter: &QF,
+    mut on_project: F,
+) -> Result<WatchState>
+where
+    A: Api,
+    RF: Filter<Repo>,
+    PF: Filter<Package>,
+    QF: Filter<Problem>,
+    F: FnMut(&str, &ProjectState) -> Result<()>,
+{
+    // Problems can only be fetched per repository, so each repository is only queried once
+    let mut problem_cache: BTreeMap<String, Vec<Problem>> = BTreeMap::new();
+    let mut projects = BTreeMap::new();
+
+    for (name, entry) in config.watchlist().iter() {
+        let project = name_mapping.project_name(name);
+        debug!("Fetching packages for '{}'", project);
+        let mut packages: BTreeMap<String, PackageState> = BTreeMap::new();
+
+        backend
+            .project(&project)?
+            .into_iter()
+            .filter(|package| package_filter.filter(package))
+            .filter(|package| {
+                if entry.repositories().is_empty() {
+                    repository_filter.filter(package.repo())
+                } else {
+                    entry.repositories().contains(package.repo())
+                }
+            })
+            .for_each(|package: Package| {
+                let state = PackageState {
+                    version: package.version().clone(),
+                    status: package.status().cloned(),
+                };
+
+                // Only the newest package of a project in a repository is watched
+                match packages.get(package.repo().deref()) {
+                    Some(known) if versioncmp::compare(&known.version, &state.version).is_ge() => {}
+                    _ => {
+                        packages.insert(package.repo().to_string(), state);
+                    }
+                }
+            });
+
+        // The repositories whose problems are watched: the configured ones, or the ones the
+        // project was found in after filtering
+        let problem_repos = if entry.repositories().is_empty() {
+            packages.keys().cloned().collect::<Vec<_>>()
+        } else {
+            entry.repositories().clone()
+        };
+
+        let mut problems = BTreeSet::new();
+        for repo in problem_repos {
+            if !problem_cache.contains_key(&repo) {
+                debug!("Fetching problems for '{}'", repo);
+                let repo_problems = backend
+                    .problems_for_repo(&repo)?
+                    .into_iter()
+                    .filter(|problem| problem_filter.filter(problem))
+                    .collect();
+                problem_cache.insert(repo.clone(), repo_problems);
+            }
+
+            problem_cache[&repo]
+                .iter()
+                .filter(|problem| **problem.project_name() == project)
+                .for_each(|problem| {
+                    problems.insert((repo.clone(), problem.problem_type().clone()));
+                });
+        }
+
+        let state = ProjectState {
+            project: Some(project).filter(|project| project != name),
+            packages,
+            problems,
+        };
+        on_project(name, &state)?;
+        projects.insert(name.clone(), state);
+    }
+
+    Ok(WatchState { projects })
+}
+
+/// Compute the changes of the project `watched` (the name on the watchlist) from the `old_state`
+/// to the `new_state`
+fn changes(watched: &str, old_state: &ProjectState, new_state: &ProjectState) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let repo = |name: &String| Repo::new(name.clone());
+    let project_name = ProjectName {
+        project: new_state
+            .project
+            .clone()
+            .unwrap_or_else(|| watched.to_string()),
+        local_name: new_state.project.as_ref().map(|_| watched.to_string()),
+    };
+
+    for (name, new_pkg) in new_state.packages.iter() {
+        match old_state.packages.get(name) {
+            None => changes.push(Change::NewPackage {
+                name: project_name.clone(),
+                repo: repo(name),
+                version: new_pkg.version.clone(),
+            }),
+
+            Some(old_pkg) => {
+                if old_pkg.version != new_pkg.version {
+                    changes.push(Change::NewVersion {
+                        name: project_name.clone(),
+                        repo: repo(name),
+                        old: old_pkg.version.clone(),
+                        new: new_pkg.version.clone(),
+                    });
+                }
+
+                if old_pkg.status != new_pkg.status {
+                    changes.push(Change::StatusTransition {
+                        name: project_name.clone(),
+                        repo: repo(name),
+                        old: old_pkg.status.clone(),
+                        new: new_pkg.status.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    old_state
+        .packages
+        .iter()
+        .filter(|(name, _)| !new_state.packages.contains_key(*name))
+        .for_each(|(name, old_pkg)| {
+            changes.push(Change::RemovedPackage {
+                name: project_name.clone(),
+                repo: repo(name),
+                version: old_pkg.version.clone(),
+            })
+        });
+
+    new_state
+        .problems
+        .difference(&old_state.problems)
+        .for_each(|(name, problem_type)| {
+            changes.push(Change::NewProblem {
+                name: project_name.clone(),
+                repo: repo(name),
+                problem_type: problem_type.clone(),
+            })
+        });
+
+    old_state
+        .problems
+        .difference(&new_state.problems)
+        .for_each(|(name, problem_type)| {
+            changes.push(Change::ResolvedProblem {
+                name: project_name.clone(),
+                repo: repo(name),
+                problem_type: problem_type.clone(),
+            })
+        });
+
+    changes
+}